@@ -0,0 +1,44 @@
+#!/bin/sh
+# Starts a local postgres with a self-signed certificate on port 5434, used by ignored tests in src/database.rs:
+#
+#   ./dev/postgres-tls.sh && cargo test tls -- --ignored
+#
+# Certificates are written to target/postgres-tls: ca.crt signs both server certificate (for "localhost") and
+# client certificate (for user "api").
+set -e
+
+cd "$(dirname "$0")/.."
+CERTS=target/postgres-tls
+mkdir -p $CERTS
+
+openssl req -new -x509 -days 365 -nodes -subj "/CN=kpiexport test ca" -keyout $CERTS/ca.key -out $CERTS/ca.crt
+
+openssl req -new -nodes -subj "/CN=localhost" -keyout $CERTS/server.key -out $CERTS/server.csr
+echo "subjectAltName=DNS:localhost" > $CERTS/server.ext
+openssl x509 -req -days 365 -in $CERTS/server.csr -CA $CERTS/ca.crt -CAkey $CERTS/ca.key -CAcreateserial \
+    -extfile $CERTS/server.ext -out $CERTS/server.crt
+
+openssl req -new -nodes -subj "/CN=api" -keyout $CERTS/client.key -out $CERTS/client.csr
+openssl x509 -req -days 365 -in $CERTS/client.csr -CA $CERTS/ca.crt -CAkey $CERTS/ca.key -CAcreateserial \
+    -out $CERTS/client.crt
+
+docker rm -f kpiexport-postgres-tls > /dev/null 2>&1 || true
+
+# postgres refuses to use a key file it does not own, so certificates are copied inside the container
+docker run -d --name kpiexport-postgres-tls -p 5434:5432 \
+    -e POSTGRES_USER=api -e POSTGRES_PASSWORD=dev -e POSTGRES_DB=api \
+    -v "$PWD/$CERTS:/certs:ro" \
+    --entrypoint sh postgres:14 -c '
+        mkdir -p /etc/postgresql-tls &&
+        cp /certs/server.crt /certs/server.key /certs/ca.crt /etc/postgresql-tls/ &&
+        chown -R postgres /etc/postgresql-tls && chmod 600 /etc/postgresql-tls/server.key &&
+        exec docker-entrypoint.sh postgres -c ssl=on \
+            -c ssl_cert_file=/etc/postgresql-tls/server.crt \
+            -c ssl_key_file=/etc/postgresql-tls/server.key \
+            -c ssl_ca_file=/etc/postgresql-tls/ca.crt'
+
+until docker exec kpiexport-postgres-tls pg_isready -h localhost -U api > /dev/null 2>&1; do
+    sleep 1
+done
+
+echo "postgres with tls is listening on localhost:5434"
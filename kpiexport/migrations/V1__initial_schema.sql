@@ -0,0 +1,37 @@
+-- tables which existed before migrations were introduced, so "if not exists" everywhere
+
+create table if not exists schedule (
+    id serial primary key,
+    group_name text not null,
+    source text not null,
+    week smallint not null,
+    day smallint not null,
+    index smallint not null,
+    names text[] not null default '{}',
+    lecturers text[] not null default '{}',
+    locations text[] not null default '{}',
+    inserted_at timestamptz not null default now(),
+    updated_at timestamptz not null default now()
+);
+
+create index if not exists schedule_group_name_idx on schedule (group_name);
+
+create table if not exists schedule_groups (
+    id serial primary key,
+    group_name text not null,
+    inserted_at timestamptz not null default now()
+);
+
+create table if not exists subjects (
+    id serial primary key,
+    link text not null,
+    emoji text not null
+);
+
+create table if not exists subject_names (
+    id serial primary key,
+    subject_id integer not null references subjects (id),
+    name text not null
+);
+
+create index if not exists subject_names_name_idx on subject_names (name);
@@ -0,0 +1,7 @@
+create table if not exists lesson_times (
+    academic_year integer not null,
+    lesson_index smallint not null,
+    start_time time not null,
+    end_time time not null,
+    primary key (academic_year, lesson_index)
+);
@@ -0,0 +1,16 @@
+create table if not exists academic_terms (
+    academic_year integer not null,
+    term smallint not null, -- 1 or 2
+    starts_on date not null,
+    ends_on date not null,
+    first_week_starts_on date not null,
+    primary key (academic_year, term)
+);
+
+create table if not exists academic_calendar_exclusions (
+    id serial primary key,
+    starts_on date not null,
+    ends_on date not null, -- inclusive
+    kind text not null, -- holiday or exam_session
+    description text not null default ''
+);
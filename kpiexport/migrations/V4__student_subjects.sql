@@ -0,0 +1,8 @@
+create table if not exists student_subjects (
+    id serial primary key,
+    group_name text not null,
+    student_name text not null,
+    subject_name text not null
+);
+
+create index if not exists student_subjects_group_name_idx on student_subjects (group_name);
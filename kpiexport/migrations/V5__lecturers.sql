@@ -0,0 +1,8 @@
+create table if not exists lecturers (
+    id serial primary key,
+    name text not null unique
+);
+
+insert into lecturers (name)
+select distinct trim(name) from schedule, unnest(lecturers) as name where trim(name) <> ''
+on conflict (name) do nothing;
@@ -0,0 +1,22 @@
+-- lecture, practice or lab
+alter table schedule add column if not exists lesson_type text;
+
+-- rozklad writes lesson type after the room, "7-418 Лек". Stored lessons are split the same way as on refresh
+-- (see GroupScheduleEntry::with_lesson_type_from_locations), otherwise the first refresh would report all of them
+-- as changed.
+update schedule set
+    lesson_type = (
+        select case m[2] when 'Лек' then 'lecture' when 'Прак' then 'practice' else 'lab' end
+        from unnest(locations) with ordinality as l(location, n),
+            regexp_match(l.location, '(^|\s)(Лек|Прак|Лаб)(\s|$)') as m
+        where m is not null
+        order by n
+        limit 1
+    ),
+    locations = array(
+        select trim(regexp_replace(regexp_replace(l.location, '(^|\s)(Лек|Прак|Лаб)(?=\s|$)', ' '), '\s+', ' ', 'g'))
+        from unnest(locations) with ordinality as l(location, n)
+        order by n
+    )
+where lesson_type is null
+    and exists (select 1 from unnest(locations) as l(location) where l.location ~ '(^|\s)(Лек|Прак|Лаб)(\s|$)');
@@ -0,0 +1,23 @@
+-- differences found when a group schedule is replaced. old_* columns are null for added lessons, new_* - for removed.
+
+create table if not exists schedule_changes (
+    id serial primary key,
+    group_name text not null,
+    change_type text not null,
+    names text[] not null,
+    old_week smallint,
+    old_day smallint,
+    old_index smallint,
+    old_lecturers text[],
+    old_locations text[],
+    old_lesson_type text,
+    new_week smallint,
+    new_day smallint,
+    new_index smallint,
+    new_lecturers text[],
+    new_locations text[],
+    new_lesson_type text,
+    detected_at timestamptz not null default now()
+);
+
+create index if not exists schedule_changes_group_name_detected_at_idx on schedule_changes (group_name, detected_at);
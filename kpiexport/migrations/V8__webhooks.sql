@@ -0,0 +1,31 @@
+-- empty group_names means every group
+create table if not exists webhook_subscriptions (
+    id serial primary key,
+    url text not null,
+    group_names text[] not null default '{}',
+    secret text not null,
+    created_at timestamptz not null default now()
+);
+
+-- payload is stored as sent, so retries have the same body and signature.
+-- status is pending (waiting for the next attempt at next_attempt_at), delivered or failed (out of attempts).
+create table if not exists webhook_deliveries (
+    id serial primary key,
+    subscription_id integer not null references webhook_subscriptions (id) on delete cascade,
+    group_name text not null,
+    payload text not null,
+    status text not null default 'pending',
+    attempts integer not null default 0,
+    next_attempt_at timestamptz not null default now(),
+    created_at timestamptz not null default now()
+);
+
+create index if not exists webhook_deliveries_pending_idx on webhook_deliveries (next_attempt_at) where status = 'pending';
+
+create table if not exists webhook_delivery_attempts (
+    id serial primary key,
+    delivery_id integer not null references webhook_deliveries (id) on delete cascade,
+    response_status integer, -- null if there was no response
+    error text,
+    attempted_at timestamptz not null default now()
+);
@@ -0,0 +1,21 @@
+-- manual fixes applied on top of the stored schedule when it is served, refresh jobs never touch them.
+-- action is add (a new lesson), cancel (lessons in the time slot are removed) or replace (non-empty fields replace
+-- those of the lesson in the time slot). valid_from and valid_until are inclusive, null means no limit.
+create table if not exists schedule_overrides (
+    id serial primary key,
+    group_name text not null,
+    action text not null,
+    week smallint not null,
+    day smallint not null,
+    index smallint not null,
+    names text[] not null default '{}',
+    lecturers text[] not null default '{}',
+    locations text[] not null default '{}',
+    lesson_type text,
+    valid_from date,
+    valid_until date,
+    comment text,
+    created_at timestamptz not null default now()
+);
+
+create index if not exists schedule_overrides_group_name_idx on schedule_overrides (group_name);
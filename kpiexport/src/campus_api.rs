@@ -0,0 +1,350 @@
+use std::sync::Mutex;
+use chrono::NaiveTime;
+use serde::Deserialize;
+use async_trait::async_trait;
+use crate::errors::RozkladParseError;
+use crate::models::schedule::*;
+use crate::models::academic_calendar::Term;
+use crate::models::lesson_times::LessonTimes;
+use crate::schedule_source::ScheduleSource;
+
+#[derive(Deserialize, Debug)]
+struct GroupsResult {
+    data: Vec<CampusGroup>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct CampusGroup {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct LessonsResult {
+    data: LessonsResultData,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LessonsResultData {
+    schedule_first_week: Vec<CampusDay>,
+    schedule_second_week: Vec<CampusDay>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CampusDay {
+    day: String,
+    pairs: Vec<CampusPair>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CampusPair {
+    name: String,
+    #[serde(default)]
+    teacher_name: String,
+    #[serde(default)]
+    place: String,
+    #[serde(rename = "type", default)]
+    lesson_type: String, // "Лек on-line", "Прак", "Лаб"
+    #[serde(default)]
+    tag: String, // "lec", "prac", "lab", sometimes empty
+    time: String, // "8.30"
+}
+
+// schedule api of campus.kpi.ua, base_url is its address (see config::kpi_campus_api_url)
+pub struct CampusApi {
+    base_url: String,
+    groups: Mutex<Vec<CampusGroup>>, // last fetched list, only reloaded for groups missing from it
+}
+
+impl CampusApi {
+
+    pub fn new(base_url: String) -> Self {
+        Self {
+            base_url,
+            groups: Mutex::new(Vec::new()),
+        }
+    }
+
+    async fn load_groups(&self, client: &reqwest::Client) -> Result<Vec<CampusGroup>, RozkladParseError> {
+        let groups = campus_groups(client, &self.base_url).await?;
+        *self.groups.lock().unwrap() = groups.clone();
+        Ok(groups)
+    }
+
+    // lessons are requested by campus group id, which is looked up in the list of groups
+    async fn group_id(&self, client: &reqwest::Client, name: &str) -> Result<String, RozkladParseError> {
+        let cached = self.groups.lock().unwrap().iter()
+            .find(|v| v.name == name)
+            .map(|v| v.id.clone());
+        if let Some(id) = cached {
+            return Ok(id);
+        }
+
+        self.load_groups(client).await?
+            .into_iter()
+            .find(|v| v.name == name)
+            .map(|v| v.id)
+            .ok_or(RozkladParseError::GroupNotFound)
+    }
+}
+
+#[async_trait]
+impl ScheduleSource for CampusApi {
+
+    fn name(&self) -> &'static str {
+        "campus"
+    }
+
+    async fn groups(&self, client: &reqwest::Client) -> Result<Vec<String>, RozkladParseError> {
+        let mut groups: Vec<String> = self.load_groups(client).await?
+            .into_iter()
+            .map(|v| v.name)
+            .collect();
+
+        groups.sort();
+        groups.dedup();
+
+        Ok(groups)
+    }
+
+    // campus only has schedule for the current term
+    async fn group_schedule(&self, client: &reqwest::Client, _term: &Term, group_name: &str) -> Result<GroupSchedule, RozkladParseError> {
+        let group_id = self.group_id(client, group_name).await?;
+        group_schedule(client, &self.base_url, &group_id).await
+    }
+}
+
+async fn campus_groups(client: &reqwest::Client, base_url: &str) -> Result<Vec<CampusGroup>, RozkladParseError> {
+    let res = client.get(format!("{}/schedule/groups", base_url))
+        .send()
+        .await?;
+
+    if res.status() != 200 {
+        return Err(RozkladParseError::RozkladApiErrored);
+    }
+
+    let groups: GroupsResult = serde_json::from_str(&res.text().await?)?;
+    Ok(groups.data)
+}
+
+async fn group_schedule(client: &reqwest::Client, base_url: &str, group_id: &str) -> Result<GroupSchedule, RozkladParseError> {
+    let res = client.get(format!("{}/schedule/lessons", base_url))
+        .query(&[("groupId", group_id)])
+        .send()
+        .await?;
+
+    if res.status() == 404 {
+        return Err(RozkladParseError::GroupNotFound);
+    }
+
+    if res.status() != 200 {
+        return Err(RozkladParseError::RozkladApiErrored);
+    }
+
+    group_schedule_from_json(&res.text().await?)
+}
+
+fn group_schedule_from_json(json: &str) -> Result<GroupSchedule, RozkladParseError> {
+    let res: LessonsResult = serde_json::from_str(json)?;
+    let lesson_times = LessonTimes::standard();
+
+    let mut entries: Vec<GroupScheduleEntry> = vec![];
+    let weeks = vec![
+        (ScheduleWeek::First, res.data.schedule_first_week),
+        (ScheduleWeek::Second, res.data.schedule_second_week),
+    ];
+
+    for (week, days) in weeks {
+        for day in days {
+            let schedule_day = match ScheduleDay::from_short_name(&day.day) {
+                Some(v) => v,
+                None => {
+                    warn!("skipping lessons of unknown day {}", day.day);
+                    continue;
+                }
+            };
+
+            for pair in day.pairs {
+                // entries are kept by lesson index, a lesson which has none can not be stored. The schedule is
+                // taken from the next source instead of showing it without the lesson.
+                let lesson_time = match lesson_start(&pair.time).and_then(|start| lesson_times.by_start(start)) {
+                    Some(v) => v,
+                    None => return Err(RozkladParseError::ScheduleFetchFailed {
+                        description: format!("{} starts at unknown time {}", pair.name, pair.time),
+                    }),
+                };
+
+                let lesson_type = lesson_type_from_tag(&pair.tag).or_else(|| LectureType::from_location(&pair.lesson_type));
+
+                // lessons split between subgroups come as separate pairs, parser keeps them in one entry
+                let existing = entries.iter_mut()
+                    .find(|v| v.week == week && v.day == schedule_day && v.index == lesson_time.index);
+
+                match existing {
+                    Some(entry) => {
+                        entry.names.push(pair.name);
+                        entry.lecturers.extend(non_empty(short_lecturer_name(&pair.teacher_name)));
+                        entry.locations.extend(non_empty(pair.place));
+                        entry.lesson_type = entry.lesson_type.or(lesson_type);
+                    },
+                    None => entries.push(
+                        GroupScheduleEntry::new(week.clone(), schedule_day.clone(), lesson_time.index)
+                            .with_names(vec![pair.name])
+                            .with_lecturers(non_empty(short_lecturer_name(&pair.teacher_name)).into_iter().collect())
+                            .with_locations(non_empty(pair.place).into_iter().collect())
+                            .with_lesson_type(lesson_type)
+                            .with_lesson_time(lesson_time)
+                    ),
+                }
+            }
+        }
+    }
+
+    entries.sort_by_key(|v| (v.week.to_index(), v.index, v.day.to_index()));
+
+    Ok(GroupSchedule { entries, source: Some(GroupScheduleSource::Campus), updated_at: None, stale: false, cancelled_entries: Vec::new(), exceptions: Vec::new() })
+}
+
+// "8.30", older responses have "08:30:00"
+fn lesson_start(time: &str) -> Option<NaiveTime> {
+    ["%H.%M", "%H:%M", "%H:%M:%S"].iter()
+        .find_map(|format| NaiveTime::parse_from_str(time.trim(), format).ok())
+}
+
+fn lesson_type_from_tag(tag: &str) -> Option<LectureType> {
+    match tag {
+        "lec" => Some(LectureType::Lecture),
+        "prac" => Some(LectureType::Practice),
+        "lab" => Some(LectureType::Lab),
+        _ => None,
+    }
+}
+
+// campus has full names, other sources have "доц. Іваненко І. І."
+fn short_lecturer_name(name: &str) -> String {
+    let mut parts = Vec::new();
+    let mut has_surname = false;
+
+    for part in name.split_whitespace() {
+        if part.ends_with('.') {
+            parts.push(part.to_string());
+        } else if !has_surname {
+            has_surname = true;
+            parts.push(part.to_string());
+        } else if let Some(initial) = part.chars().next() {
+            parts.push(format!("{}.", initial));
+        }
+    }
+
+    parts.join(" ")
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+// campus api test
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use more_asserts::assert_gt;
+    use crate::rozklad_mock::{start_rozklad_mock, CAMPUS_IP82_GROUP_ID};
+    use crate::test_fixtures::{assert_matches_golden, fixture};
+
+    #[test]
+    fn lessons() {
+        let schedule = group_schedule_from_json(&fixture("campus_api/lessons.json")).unwrap();
+
+        assert!(matches!(schedule.source, Some(GroupScheduleSource::Campus)));
+        assert_matches_golden(&schedule.entries, "campus_api/lessons");
+    }
+
+    #[test]
+    fn lesson_type_falls_back_to_type_name() {
+        let schedule = group_schedule_from_json(&fixture("campus_api/lessons.json")).unwrap();
+        let entry = schedule.entries.iter().find(|v| v.names == vec!["Операційні системи"]).unwrap();
+
+        assert_eq!(entry.lesson_type, Some(LectureType::Lab));
+        assert_eq!(entry.index, 5);
+        assert_eq!(entry.start_time, Some(NaiveTime::from_hms(18, 30, 0)));
+    }
+
+    #[test]
+    fn lesson_at_unknown_time_fails_schedule() {
+        let schedule = group_schedule_from_json(r#"{"data":{"scheduleFirstWeek":[{"day":"Пн","pairs":[
+            {"name":"Бази даних","time":"7.00"},
+            {"name":"Англійська мова","time":"10:25:00"}
+        ]}],"scheduleSecondWeek":[]}}"#);
+
+        assert!(matches!(schedule, Err(RozkladParseError::ScheduleFetchFailed { .. })));
+
+        let schedule = group_schedule_from_json(r#"{"data":{"scheduleFirstWeek":[{"day":"Пн","pairs":[
+            {"name":"Англійська мова","time":"10:25:00"}
+        ]}],"scheduleSecondWeek":[]}}"#).unwrap();
+        assert_eq!(schedule.entries[0].index, 1);
+    }
+
+    #[test]
+    fn lecturer_names_are_shortened() {
+        assert_eq!(short_lecturer_name("доц. Іваненко Іван Іванович"), "доц. Іваненко І. І.");
+        assert_eq!(short_lecturer_name("ст.вик. Петренко Петро"), "ст.вик. Петренко П.");
+        assert_eq!(short_lecturer_name("Іваненко І. І."), "Іваненко І. І.");
+        assert_eq!(short_lecturer_name(""), "");
+    }
+
+    #[test]
+    fn unexpected_response() {
+        assert!(matches!(
+            group_schedule_from_json(r#"{"message":"Group not found"}"#),
+            Err(RozkladParseError::ResponseParseFailed { .. })
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn group_schedule_is_fetched_by_group_id() {
+        let source = CampusApi::new(format!("{}/campus", start_rozklad_mock()));
+        let schedule = source.group_schedule(&reqwest::Client::new(), &Term::First, "ІП-82").await.unwrap();
+
+        assert_matches_golden(&schedule.entries, "campus_api/lessons");
+    }
+
+    #[actix_rt::test]
+    async fn group_ids_are_cached() {
+        let source = CampusApi::new(format!("{}/campus", start_rozklad_mock()));
+        let client = reqwest::Client::new();
+        source.groups(&client).await.unwrap();
+
+        // ІП-83 is not in the list campus returns, so it can only come from the cached one
+        source.groups.lock().unwrap()[0].name = "ІП-83".into();
+        source.groups.lock().unwrap()[0].id = CAMPUS_IP82_GROUP_ID.into();
+
+        let schedule = source.group_schedule(&client, &Term::First, "ІП-83").await.unwrap();
+        assert_matches_golden(&schedule.entries, "campus_api/lessons");
+    }
+
+    #[actix_rt::test]
+    async fn unknown_group() {
+        let source = CampusApi::new(format!("{}/campus", start_rozklad_mock()));
+
+        assert!(matches!(
+            source.group_schedule(&reqwest::Client::new(), &Term::First, "ІП-83").await,
+            Err(RozkladParseError::GroupNotFound)
+        ));
+        assert_eq!(source.groups(&reqwest::Client::new()).await.unwrap(), vec!["ІП-81", "ІП-82", "КВ-91"]);
+    }
+
+    // uses live api.campus.kpi.ua, run with --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn campus_group_schedule_ip82() {
+        let source = CampusApi::new("https://api.campus.kpi.ua".into());
+        assert_gt!(source.group_schedule(&reqwest::Client::new(), &Term::First, "ІП-82").await.unwrap().entries.len(), 0);
+    }
+}
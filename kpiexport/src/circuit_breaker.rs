@@ -0,0 +1,106 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// stops calling something which keeps failing. After failure_threshold consecutive failures requests are not
+// allowed for retry_after, then one trial request is let through: success closes the circuit, failure opens it again.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    retry_after: Duration,
+    state: Mutex<CircuitState>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CircuitState {
+    Closed { consecutive_failures: u32 },
+    Open { until: Instant },
+    HalfOpen { since: Instant }, // trial request is running, if it never finishes another one is allowed after retry_after
+}
+
+impl CircuitBreaker {
+
+    pub fn new(failure_threshold: u32, retry_after: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            retry_after,
+            state: Mutex::new(CircuitState::Closed { consecutive_failures: 0 }),
+        }
+    }
+
+    pub fn allows_request(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        match *state {
+            CircuitState::Closed { .. } => true,
+            CircuitState::Open { until } if Instant::now() < until => false,
+            CircuitState::HalfOpen { since } if since.elapsed() < self.retry_after => false,
+            _ => {
+                *state = CircuitState::HalfOpen { since: Instant::now() };
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = CircuitState::Closed { consecutive_failures: 0 };
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        let consecutive_failures = match *state {
+            CircuitState::Closed { consecutive_failures } => consecutive_failures + 1,
+            _ => self.failure_threshold,
+        };
+
+        *state = if consecutive_failures >= self.failure_threshold {
+            CircuitState::Open { until: Instant::now() + self.retry_after }
+        } else {
+            CircuitState::Closed { consecutive_failures }
+        };
+    }
+
+    // true while requests are not allowed or only a trial one is
+    pub fn is_open(&self) -> bool {
+        !matches!(*self.state.lock().unwrap(), CircuitState::Closed { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let circuit = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        circuit.record_failure();
+        circuit.record_success();
+        circuit.record_failure();
+        assert!(circuit.allows_request());
+
+        circuit.record_failure();
+        assert!(circuit.is_open());
+        assert!(!circuit.allows_request());
+    }
+
+    #[test]
+    fn lets_one_trial_request_through_after_retry_delay() {
+        let circuit = CircuitBreaker::new(1, Duration::from_millis(20));
+
+        circuit.record_failure();
+        assert!(!circuit.allows_request());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(circuit.allows_request());
+        assert!(!circuit.allows_request());
+
+        circuit.record_failure();
+        assert!(!circuit.allows_request());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(circuit.allows_request());
+        circuit.record_success();
+        assert!(!circuit.is_open());
+        assert!(circuit.allows_request());
+    }
+}
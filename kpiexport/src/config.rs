@@ -1,10 +1,50 @@
 use std::env::var;
+use std::time::Duration;
 
 // http server
 pub fn bind_address() -> String {
     var("BIND_ADDRESS").unwrap_or("0.0.0.0:8080".into())
 }
 
+// rozklad.kpi.ua and its api (without trailing slash), can point to a mirror or a local stand-in
+pub fn rozklad_url() -> String {
+    var("ROZKLAD_URL").unwrap_or("http://rozklad.kpi.ua".into())
+}
+
+pub fn rozklad_api_url() -> String {
+    var("ROZKLAD_API_URL").unwrap_or("https://api.rozklad.org.ua/v2".into())
+}
+
+pub fn kpi_campus_api_url() -> String {
+    var("KPI_CAMPUS_API_URL").unwrap_or("https://api.campus.kpi.ua".into())
+}
+
+// schedule sources in the order they are tried: parser (rozklad.kpi.ua pages), api (api.rozklad.org.ua) and
+// campus (api.campus.kpi.ua)
+pub fn schedule_sources() -> Vec<String> {
+    var("SCHEDULE_SOURCES").unwrap_or("parser,api,campus".into())
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+// consecutive failures after which a source is skipped for schedule_source_retry_after
+pub fn schedule_source_failure_threshold() -> u32 {
+    var("SCHEDULE_SOURCE_FAILURE_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5)
+}
+
+pub fn schedule_source_retry_after() -> Duration {
+    Duration::from_secs(var("SCHEDULE_SOURCE_RETRY_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60)
+    )
+}
+
 // database
 pub fn postgres_username() -> String {
     var("POSTGRES_USER").unwrap_or("api".into())
@@ -28,3 +68,91 @@ pub fn postgres_port() -> u16 {
 pub fn postgres_db() -> String {
     var("POSTGRES_DB").unwrap_or("api".into())
 }
+
+// disable, prefer, require or verify-full
+pub fn postgres_ssl_mode() -> String {
+    var("POSTGRES_SSLMODE").unwrap_or("disable".into())
+}
+
+// paths to PEM files
+pub fn postgres_ssl_root_cert() -> Option<String> {
+    var("POSTGRES_SSLROOTCERT").ok()
+}
+
+pub fn postgres_ssl_cert() -> Option<String> {
+    var("POSTGRES_SSLCERT").ok()
+}
+
+pub fn postgres_ssl_key() -> Option<String> {
+    var("POSTGRES_SSLKEY").ok()
+}
+
+// database connection pool (web server only, jobs use a single connection)
+pub fn postgres_pool_size() -> usize {
+    var("POSTGRES_POOL_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(16)
+}
+
+// how long a request waits for a free connection before getting 503
+pub fn postgres_pool_wait_timeout() -> Duration {
+    duration_millis_from_env("POSTGRES_POOL_WAIT_TIMEOUT_MS", 5000)
+}
+
+pub fn postgres_pool_create_timeout() -> Duration {
+    duration_millis_from_env("POSTGRES_POOL_CREATE_TIMEOUT_MS", 5000)
+}
+
+pub fn postgres_pool_recycle_timeout() -> Duration {
+    duration_millis_from_env("POSTGRES_POOL_RECYCLE_TIMEOUT_MS", 5000)
+}
+
+// bearer token for admin endpoints (webhook subscriptions), they are disabled when it is not set
+pub fn admin_token() -> Option<String> {
+    var("ADMIN_TOKEN").ok().filter(|v| !v.is_empty())
+}
+
+// in-memory cache of group schedule responses, 0 disables it
+pub fn schedule_cache_capacity() -> usize {
+    var("SCHEDULE_CACHE_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(512)
+}
+
+// cached schedules are also dropped when refresh jobs save a new one, this only limits staleness of
+// everything else (subjects, lesson times)
+pub fn schedule_cache_ttl() -> Duration {
+    Duration::from_secs(var("SCHEDULE_CACHE_TTL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(600)
+    )
+}
+
+// stored schedules older than this are served as stale (with a warning) and fetched again in background
+pub fn schedule_max_age() -> chrono::Duration {
+    chrono::Duration::days(var("SCHEDULE_MAX_AGE_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(14)
+    )
+}
+
+// how often a stale schedule of one group is fetched again while rozklad keeps failing
+pub fn stale_schedule_refresh_interval() -> Duration {
+    Duration::from_secs(var("STALE_SCHEDULE_REFRESH_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300)
+    )
+}
+
+fn duration_millis_from_env(name: &str, default_millis: u64) -> Duration {
+    Duration::from_millis(var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default_millis)
+    )
+}
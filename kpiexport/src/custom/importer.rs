@@ -1,2 +1,245 @@
-pub fn import_plan() {
-}
\ No newline at end of file
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use calamine::{open_workbook_auto, DataType, Range, Reader};
+use crate::errors::ImportError;
+use crate::models::schedule::{GroupSchedule, GroupScheduleEntry, GroupScheduleSource, LectureType, ScheduleDay, ScheduleWeek};
+
+// Schedules departments send as xlsx. Only the first worksheet is read, its first row is a header and the rest
+// are lessons, one per row, in columns:
+//
+//   A group     "ІП-82", required
+//   B week      1 or 2
+//   C day       1-7 or "Пн".."Нд"
+//   D index     lesson number as on rozklad, the first lesson is 1
+//   E subject   required
+//   F lecturer  several are separated with ";"
+//   G room      several are separated with ";"
+//   H type      "Лек", "Прак", "Лаб" (or lecture, practice, lab), may be empty
+//
+// Rows with the same group, week, day and index are one lesson split between subgroups. Empty rows are skipped.
+const COLUMNS: usize = 8;
+
+#[derive(Debug)]
+pub struct RowError {
+    pub row: usize, // as numbered in the spreadsheet, header is row 1
+    pub message: String,
+}
+
+// schedules are only saved when there are no errors, so that a half imported file does not replace anything
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub schedules: BTreeMap<String, GroupSchedule>,
+    pub errors: Vec<RowError>,
+}
+
+impl fmt::Display for RowError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}: {}", self.row, self.message)
+    }
+}
+
+pub fn read_schedule_xlsx<P: AsRef<Path>>(path: P) -> Result<ImportReport, ImportError> {
+    let mut workbook = open_workbook_auto(path)?;
+    let range = match workbook.worksheet_range_at(0) {
+        Some(v) => v?,
+        None => return Err(ImportError::NoWorksheet),
+    };
+
+    Ok(schedules_from_range(&range))
+}
+
+fn schedules_from_range(range: &Range<DataType>) -> ImportReport {
+    let first_row = range.start().map(|v| v.0 as usize).unwrap_or(0);
+
+    let rows = range.rows()
+        .enumerate()
+        .map(|(i, cells)| (first_row + i + 1, cells))
+        .skip(1);
+
+    schedules_from_rows(rows)
+}
+
+fn schedules_from_rows<'a>(rows: impl Iterator<Item = (usize, &'a [DataType])>) -> ImportReport {
+    let mut report = ImportReport::default();
+
+    for (row, cells) in rows {
+        let mut cells: Vec<String> = cells.iter().map(cell_text).collect();
+        if cells.iter().all(String::is_empty) {
+            continue;
+        }
+        cells.resize(COLUMNS.max(cells.len()), String::new());
+
+        let (group_name, entry) = match entry_from_row(&cells) {
+            Ok(v) => v,
+            Err(message) => {
+                report.errors.push(RowError { row, message });
+                continue;
+            }
+        };
+
+        let schedule = report.schedules.entry(group_name).or_insert_with(|| GroupSchedule {
+            entries: Vec::new(),
+            source: Some(GroupScheduleSource::Custom),
+            updated_at: None,
+            stale: false,
+            cancelled_entries: Vec::new(),
+            exceptions: Vec::new(),
+        });
+
+        let existing = schedule.entries.iter_mut()
+            .find(|v| v.week == entry.week && v.day == entry.day && v.index == entry.index);
+
+        match existing {
+            Some(existing) => {
+                existing.names.extend(entry.names);
+                existing.lecturers.extend(entry.lecturers);
+                existing.locations.extend(entry.locations);
+                existing.lesson_type = existing.lesson_type.or(entry.lesson_type);
+            },
+            None => schedule.entries.push(entry),
+        }
+    }
+
+    // same order as schedules from rozklad
+    for schedule in report.schedules.values_mut() {
+        schedule.entries.sort_by_key(|v| (v.week.to_index(), v.index, v.day.to_index()));
+    }
+
+    report
+}
+
+fn entry_from_row(cells: &[String]) -> Result<(String, GroupScheduleEntry), String> {
+    let group_name = required(&cells[0], "group")?;
+    let week = match number(&cells[1], "week", 1, 2)? {
+        1 => ScheduleWeek::First,
+        _ => ScheduleWeek::Second,
+    };
+    let day = match ScheduleDay::from_short_name(&cells[2]) {
+        Some(v) => v,
+        None => ScheduleDay::from_api_index(number(&cells[2], "day", 1, 7)?),
+    };
+    let index = number(&cells[3], "index", 1, u8::MAX)? - 1;
+    let subject = required(&cells[4], "subject")?;
+
+    let lesson_type = match cells[7].as_str() {
+        "" => None,
+        other => match LectureType::from_string(other).or_else(|| LectureType::from_location(other)) {
+            Some(v) => Some(v),
+            None => return Err(format!("unknown lesson type \"{}\"", other)),
+        },
+    };
+
+    let entry = GroupScheduleEntry::new(week, day, index)
+        .with_names(vec![subject])
+        .with_lecturers(list(&cells[5]))
+        .with_locations(list(&cells[6]))
+        .with_lesson_type(lesson_type);
+
+    Ok((group_name, entry))
+}
+
+// numbers typed into excel are floats, 1 is read as "1" and not "1.0"
+fn cell_text(cell: &DataType) -> String {
+    match cell {
+        DataType::Float(v) if v.fract() == 0.0 => format!("{}", *v as i64),
+        DataType::Empty => String::new(),
+        other => other.to_string().trim().to_string(),
+    }
+}
+
+fn required(value: &str, column: &str) -> Result<String, String> {
+    if value.is_empty() {
+        Err(format!("{} is empty", column))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+fn number(value: &str, column: &str, min: u8, max: u8) -> Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(v) if v >= min && v <= max => Ok(v),
+        _ => Err(format!("{} should be a number from {} to {}, got \"{}\"", column, min, max, value)),
+    }
+}
+
+fn list(value: &str) -> Vec<String> {
+    value.split(';')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{assert_matches_golden, fixture_path};
+
+    fn row(cells: &[&str]) -> Vec<DataType> {
+        cells.iter()
+            .map(|v| if v.is_empty() { DataType::Empty } else { DataType::String(v.to_string()) })
+            .collect()
+    }
+
+    fn report(rows: &[Vec<DataType>]) -> ImportReport {
+        schedules_from_rows(rows.iter().enumerate().map(|(i, cells)| (i + 2, cells.as_slice())))
+    }
+
+    #[test]
+    fn schedule_xlsx() {
+        let report = read_schedule_xlsx(fixture_path("custom_import/schedule.xlsx")).unwrap();
+
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_matches_golden(&report.schedules, "custom_import/schedule");
+        assert!(report.schedules.values().all(|v| matches!(v.source, Some(GroupScheduleSource::Custom))));
+    }
+
+    #[test]
+    fn invalid_rows_are_reported_with_row_numbers() {
+        let report = read_schedule_xlsx(fixture_path("custom_import/invalid_rows.xlsx")).unwrap();
+        let errors: Vec<String> = report.errors.iter().map(|v| v.to_string()).collect();
+
+        assert_eq!(errors, vec![
+            "row 3: week should be a number from 1 to 2, got \"3\"",
+            "row 4: subject is empty",
+            "row 6: unknown lesson type \"Семінар\"",
+        ]);
+        assert_eq!(report.schedules["ІП-82"].entries.len(), 2);
+    }
+
+    #[test]
+    fn split_lessons_are_merged() {
+        let report = report(&[
+            row(&["ІП-82", "1", "Вт", "2", "Англійська мова", "вик. Коваленко О. М.", "7-301", "Прак"]),
+            row(&["ІП-82", "1", "2", "2", "Німецька мова", "вик. Шевчук А. В.", "7-302", "practice"]),
+        ]);
+        let entries = &report.schedules["ІП-82"].entries;
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].index, 1);
+        assert_eq!(entries[0].names, vec!["Англійська мова", "Німецька мова"]);
+        assert_eq!(entries[0].locations, vec!["7-301", "7-302"]);
+        assert_eq!(entries[0].lesson_type, Some(LectureType::Practice));
+    }
+
+    #[test]
+    fn numbers_typed_into_cells() {
+        let report = report(&[vec![
+            DataType::String("ІП-82".to_string()), DataType::Float(2.0), DataType::Int(5), DataType::Float(1.0),
+            DataType::String("Бази даних".to_string()),
+        ]]);
+        let entry = &report.schedules["ІП-82"].entries[0];
+
+        assert!(report.errors.is_empty());
+        assert_eq!((entry.week.to_index(), entry.day.to_index(), entry.index), (1, 4, 0));
+        assert!(entry.lecturers.is_empty());
+        assert_eq!(entry.lesson_type, None);
+    }
+
+    #[test]
+    fn missing_file() {
+        assert!(matches!(read_schedule_xlsx(fixture_path("custom_import/missing.xlsx")), Err(ImportError::OpenFailed { .. })));
+    }
+}
@@ -1,16 +1,100 @@
-use tokio_postgres::{connect, Client, Error, NoTls};
+use std::fs::read;
+use std::future::poll_fn;
+use tokio_postgres::{AsyncMessage, Client, Config, config::SslMode as PostgresSslMode};
 use thiserror::Error;
-use crate::config::{postgres_db, postgres_host, postgres_password, postgres_port, postgres_username};
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod, Runtime};
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use crate::config::{
+    postgres_db,
+    postgres_host,
+    postgres_password,
+    postgres_port,
+    postgres_username,
+    postgres_ssl_mode,
+    postgres_ssl_root_cert,
+    postgres_ssl_cert,
+    postgres_ssl_key,
+    postgres_pool_size,
+    postgres_pool_wait_timeout,
+    postgres_pool_create_timeout,
+    postgres_pool_recycle_timeout,
+};
 use crate::models::schedule::SubjectId;
 
 #[derive(Error, Debug)]
 pub enum DatabaseError {
     #[error("postgres query failed: {0}")]
     QueryFailed(String),
+    #[error("failed to connect to postgres: {0}")]
+    ConnectionFailed(String),
+    #[error("invalid postgres config: {0}")]
+    InvalidConfig(String),
 }
 
-pub async fn database_connection() -> Result<Client, Error> {
-    let (client, connection) = connect(&config_str(), NoTls).await?;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SslMode {
+    Disable,
+    Prefer,
+    Require,
+    VerifyFull,
+}
+
+// same as in libpq: "prefer" and "require" only encrypt the connection (server certificate is checked if root
+// certificate is set, host name is not), "verify-full" checks both server certificate and host name.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub ssl_mode: SslMode,
+    pub root_cert_path: Option<String>,
+    pub client_cert_path: Option<String>,
+    pub client_key_path: Option<String>,
+}
+
+impl SslMode {
+
+    pub fn from_string(name: &str) -> Option<Self> {
+        match name {
+            "disable" => Some(Self::Disable),
+            "prefer" => Some(Self::Prefer),
+            "require" => Some(Self::Require),
+            "verify-full" => Some(Self::VerifyFull),
+            _ => None
+        }
+    }
+
+    // certificate checks are done by tls connector, postgres only needs to know if tls is required
+    fn to_postgres(self) -> PostgresSslMode {
+        match self {
+            Self::Disable => PostgresSslMode::Disable,
+            Self::Prefer => PostgresSslMode::Prefer,
+            Self::Require | Self::VerifyFull => PostgresSslMode::Require,
+        }
+    }
+}
+
+impl TlsConfig {
+
+    pub fn from_env() -> Result<Self, DatabaseError> {
+        let ssl_mode = postgres_ssl_mode();
+
+        Ok(Self {
+            ssl_mode: SslMode::from_string(&ssl_mode)
+                .ok_or_else(|| DatabaseError::InvalidConfig(format!("unknown sslmode: {}", ssl_mode)))?,
+            root_cert_path: postgres_ssl_root_cert(),
+            client_cert_path: postgres_ssl_cert(),
+            client_key_path: postgres_ssl_key(),
+        })
+    }
+}
+
+pub async fn database_connection() -> Result<Client, DatabaseError> {
+    let tls = TlsConfig::from_env()?;
+    connect_to_database(postgres_config(&tls)?, &tls).await
+}
+
+pub async fn connect_to_database(config: Config, tls: &TlsConfig) -> Result<Client, DatabaseError> {
+    let (client, connection) = config.connect(tls_connector(tls)?).await
+        .map_err(|err| DatabaseError::ConnectionFailed(err.to_string()))?;
 
     actix_rt::spawn(async move {
         if let Err(e) = connection.await {
@@ -21,6 +105,105 @@ pub async fn database_connection() -> Result<Client, Error> {
     Ok(client)
 }
 
+// calls on_notification with the payload of every notification sent to the channel. Returns when the
+// connection is closed, notifications sent while reconnecting are lost.
+pub async fn listen_for_notifications<F: Fn(&str)>(channel: &str, on_notification: F) -> Result<(), DatabaseError> {
+    let tls = TlsConfig::from_env()?;
+    let (client, mut connection) = postgres_config(&tls)?.connect(tls_connector(&tls)?).await
+        .map_err(|err| DatabaseError::ConnectionFailed(err.to_string()))?;
+
+    // notifications are only available when polling the connection directly
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<String>();
+    actix_rt::spawn(async move {
+        while let Some(message) = poll_fn(|cx| connection.poll_message(cx)).await {
+            match message {
+                Ok(AsyncMessage::Notification(notification)) => {
+                    if sender.send(notification.payload().to_string()).is_err() {
+                        break;
+                    }
+                },
+                Ok(_) => {},
+                Err(err) => {
+                    error!("database connection error: {}", err);
+                    break;
+                }
+            }
+        }
+    });
+
+    client.batch_execute(&format!("listen {}", channel)).await
+        .map_err(|err| DatabaseError::QueryFailed(err.to_string()))?;
+
+    while let Some(payload) = receiver.recv().await {
+        on_notification(&payload);
+    }
+
+    Ok(())
+}
+
+pub type DatabasePool = Pool;
+
+// shared by all web server handlers, connections are opened lazily
+pub fn database_pool() -> Result<DatabasePool, DatabaseError> {
+    let tls = TlsConfig::from_env()?;
+
+    let manager = Manager::from_config(postgres_config(&tls)?, tls_connector(&tls)?, ManagerConfig {
+        recycling_method: RecyclingMethod::Fast,
+    });
+
+    Pool::builder(manager)
+        .max_size(postgres_pool_size())
+        .wait_timeout(Some(postgres_pool_wait_timeout()))
+        .create_timeout(Some(postgres_pool_create_timeout()))
+        .recycle_timeout(Some(postgres_pool_recycle_timeout()))
+        .runtime(Runtime::Tokio1)
+        .build()
+        .map_err(|err| DatabaseError::InvalidConfig(err.to_string()))
+}
+
+pub fn postgres_config(tls: &TlsConfig) -> Result<Config, DatabaseError> {
+    let mut config: Config = config_str().parse()
+        .map_err(|err: tokio_postgres::Error| DatabaseError::InvalidConfig(err.to_string()))?;
+
+    config.ssl_mode(tls.ssl_mode.to_postgres());
+
+    Ok(config)
+}
+
+// connector is not used when sslmode is "disable"
+pub fn tls_connector(tls: &TlsConfig) -> Result<MakeTlsConnector, DatabaseError> {
+    let mut builder = TlsConnector::builder();
+
+    if let Some(root_cert_path) = &tls.root_cert_path {
+        let certificate = Certificate::from_pem(&read_file(root_cert_path)?)
+            .map_err(|err| DatabaseError::InvalidConfig(format!("failed to parse root certificate: {}", err)))?;
+        builder.add_root_certificate(certificate);
+    }
+
+    match (&tls.client_cert_path, &tls.client_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let identity = Identity::from_pkcs8(&read_file(cert_path)?, &read_file(key_path)?)
+                .map_err(|err| DatabaseError::InvalidConfig(format!("failed to parse client certificate: {}", err)))?;
+            builder.identity(identity);
+        },
+        (None, None) => {},
+        _ => return Err(DatabaseError::InvalidConfig("both client certificate and key should be set".to_string())),
+    }
+
+    if tls.ssl_mode != SslMode::VerifyFull {
+        builder.danger_accept_invalid_hostnames(true);
+        builder.danger_accept_invalid_certs(tls.root_cert_path.is_none());
+    }
+
+    builder.build()
+        .map(MakeTlsConnector::new)
+        .map_err(|err| DatabaseError::InvalidConfig(format!("failed to create tls connector: {}", err)))
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, DatabaseError> {
+    read(path).map_err(|err| DatabaseError::InvalidConfig(format!("failed to read {}: {}", path, err)))
+}
+
 pub async fn subject_id_by_name(database: &Client, subject_name: &str) -> Result<Option<SubjectId>, DatabaseError> {
     let res = match database.query(
         "select subject_id from subject_names where name = $1 limit 1",
@@ -48,3 +231,97 @@ pub fn config_str() -> String {
         postgres_db()
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // see dev/postgres-tls.sh
+    const TLS_POSTGRES_CONFIG: &str = "host=localhost port=5434 user=api password=dev dbname=api";
+
+    fn test_certificate(name: &str) -> Option<String> {
+        Some(format!("{}/target/postgres-tls/{}", env!("CARGO_MANIFEST_DIR"), name))
+    }
+
+    fn tls_config(ssl_mode: SslMode) -> TlsConfig {
+        TlsConfig {
+            ssl_mode,
+            root_cert_path: None,
+            client_cert_path: None,
+            client_key_path: None,
+        }
+    }
+
+    async fn connect_to_test_database(tls: &TlsConfig) -> Result<Client, DatabaseError> {
+        let mut config: Config = TLS_POSTGRES_CONFIG.parse().unwrap();
+        config.ssl_mode(tls.ssl_mode.to_postgres());
+
+        connect_to_database(config, tls).await
+    }
+
+    async fn is_connection_encrypted(client: &Client) -> bool {
+        client.query_one("select ssl from pg_stat_ssl where pid = pg_backend_pid()", &[]).await
+            .unwrap()
+            .get("ssl")
+    }
+
+    #[test]
+    fn ssl_mode_is_parsed() {
+        assert_eq!(SslMode::from_string("verify-full"), Some(SslMode::VerifyFull));
+        assert_eq!(SslMode::from_string("disable"), Some(SslMode::Disable));
+        assert_eq!(SslMode::from_string("verify-ca"), None);
+    }
+
+    #[test]
+    fn client_certificate_requires_key() {
+        let tls = TlsConfig {
+            client_cert_path: test_certificate("client.crt"),
+            ..tls_config(SslMode::Require)
+        };
+
+        assert!(matches!(tls_connector(&tls), Err(DatabaseError::InvalidConfig(_))));
+    }
+
+    #[actix_rt::test]
+    #[ignore]
+    async fn tls_connection_with_self_signed_certificate() {
+        let client = connect_to_test_database(&tls_config(SslMode::Require)).await.unwrap();
+        assert!(is_connection_encrypted(&client).await);
+
+        let client = connect_to_test_database(&tls_config(SslMode::Prefer)).await.unwrap();
+        assert!(is_connection_encrypted(&client).await);
+
+        let client = connect_to_test_database(&tls_config(SslMode::Disable)).await.unwrap();
+        assert!(!is_connection_encrypted(&client).await);
+    }
+
+    #[actix_rt::test]
+    #[ignore]
+    async fn tls_verify_full_checks_server_certificate() {
+        assert!(connect_to_test_database(&tls_config(SslMode::VerifyFull)).await.is_err());
+
+        let tls = TlsConfig {
+            root_cert_path: test_certificate("ca.crt"),
+            ..tls_config(SslMode::VerifyFull)
+        };
+        let client = connect_to_test_database(&tls).await.unwrap();
+        assert!(is_connection_encrypted(&client).await);
+    }
+
+    #[actix_rt::test]
+    #[ignore]
+    async fn tls_client_certificate() {
+        let tls = TlsConfig {
+            root_cert_path: test_certificate("ca.crt"),
+            client_cert_path: test_certificate("client.crt"),
+            client_key_path: test_certificate("client.key"),
+            ..tls_config(SslMode::VerifyFull)
+        };
+        let client = connect_to_test_database(&tls).await.unwrap();
+
+        let client_dn: Option<String> = client.query_one("select client_dn from pg_stat_ssl where pid = pg_backend_pid()", &[]).await
+            .unwrap()
+            .get("client_dn");
+        assert_eq!(client_dn, Some("/CN=api".to_string()));
+    }
+}
@@ -5,11 +5,29 @@ custom_error!{pub RozkladParseError
     HtmlParseFailed { description: String } = "html parse failed: {}",
     RozkladErrored = "rozklad errored",
     RozkladApiErrored = "rozklad api errored",
+    ResponseParseFailed { source: serde_json::Error } = "failed to parse rozklad api response: {source}",
     FailedToParseGroupId = "failed to parse group id",
     RozkladParseError { source: std::num::ParseIntError } = "failed to parse number",
+    ScheduleFetchFailed { description: String } = "failed to fetch schedule: {description}",
+    GroupNotFound = "group not found",
+    SourceUnsupported = "not supported by this schedule source",
+    NoSourceAvailable = "no schedule source is available",
+}
+
+impl RozkladParseError {
+
+    // false when the source works, but does not have what was asked for. Only source failures open its circuit.
+    pub fn is_source_failure(&self) -> bool {
+        !matches!(self, Self::FailedToParseGroupId | Self::GroupNotFound | Self::SourceUnsupported)
+    }
 }
 
 custom_error! {pub PersistenceError
     FailedToSave = "failed to save schedule to database",
-    FailedToLoad = "failed to load schedule from database"
-}
\ No newline at end of file
+    FailedToLoad = "failed to load schedule from database",
+    QueryFailed { source: tokio_postgres::Error } = "database query failed: {source}",
+}
+custom_error! {pub ImportError
+    OpenFailed { source: calamine::Error } = "failed to read xlsx: {source}",
+    NoWorksheet = "xlsx has no worksheets",
+}
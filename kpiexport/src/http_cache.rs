@@ -0,0 +1,114 @@
+use std::time::SystemTime;
+use actix_web::{HttpRequest, HttpResponse};
+use actix_web::http::header::{
+    self, CacheControl, CacheDirective, ContentType, ETag, EntityTag, Header, HttpDate, IfModifiedSince, IfNoneMatch, LastModified,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+// json response with validators, so clients and CDNs can revalidate it with If-None-Match or If-Modified-Since
+// and get 304 without the body
+pub fn cacheable_json<T: Serialize>(req: &HttpRequest, value: &T, last_modified: Option<DateTime<Utc>>, max_age: u32) -> HttpResponse {
+    let body = match serde_json::to_vec(value) {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to serialize response: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    let etag = EntityTag::new_strong(format!("{:x}", Sha256::digest(&body)));
+    // header has only seconds, database timestamps have microseconds
+    let last_modified = last_modified.map(|v| HttpDate::from(SystemTime::from(Utc.timestamp(v.timestamp(), 0))));
+    let not_modified = is_not_modified(req, &etag, last_modified);
+
+    let mut response = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+
+    response
+        .insert_header(ETag(etag))
+        .insert_header(CacheControl(vec![CacheDirective::Public, CacheDirective::MaxAge(max_age)]));
+
+    if let Some(last_modified) = last_modified {
+        response.insert_header(LastModified(last_modified));
+    }
+
+    if not_modified {
+        response.finish()
+    } else {
+        response.insert_header(ContentType::json()).body(body)
+    }
+}
+
+fn is_not_modified(req: &HttpRequest, etag: &EntityTag, last_modified: Option<HttpDate>) -> bool {
+    // If-Modified-Since is ignored when If-None-Match is present (RFC 7232, section 6)
+    if req.headers().contains_key(header::IF_NONE_MATCH) {
+        return match IfNoneMatch::parse(req) {
+            Ok(IfNoneMatch::Any) => true,
+            Ok(IfNoneMatch::Items(items)) => items.iter().any(|v| v.weak_eq(etag)),
+            Err(_) => false,
+        };
+    }
+
+    match (IfModifiedSince::parse(req), last_modified) {
+        (Ok(IfModifiedSince(since)), Some(last_modified)) => SystemTime::from(last_modified) <= SystemTime::from(since),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    fn last_modified() -> DateTime<Utc> {
+        Utc.ymd(2021, 9, 1).and_hms_milli(10, 0, 0, 500)
+    }
+
+    #[test]
+    fn matching_etag_is_not_modified() {
+        let response = cacheable_json(&TestRequest::default().to_http_request(), &vec!["ІП-82"], None, 60);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(header::CACHE_CONTROL).unwrap(), "public, max-age=60");
+        let etag = response.headers().get(header::ETAG).unwrap().clone();
+
+        let req = TestRequest::default().insert_header((header::IF_NONE_MATCH, etag.clone())).to_http_request();
+        let response = cacheable_json(&req, &vec!["ІП-82"], None, 60);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), &etag);
+
+        let req = TestRequest::default().insert_header((header::IF_NONE_MATCH, etag)).to_http_request();
+        let response = cacheable_json(&req, &vec!["ІП-81"], None, 60);
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn if_modified_since_is_compared_to_last_modified() {
+        let since = |date: DateTime<Utc>| TestRequest::default()
+            .insert_header(IfModifiedSince(HttpDate::from(SystemTime::from(date))))
+            .to_http_request();
+
+        let response = cacheable_json(&since(last_modified()), &vec!["ІП-82"], Some(last_modified()), 60);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get(header::LAST_MODIFIED).unwrap(), "Wed, 01 Sep 2021 10:00:00 GMT");
+
+        let response = cacheable_json(&since(Utc.ymd(2021, 8, 31).and_hms(10, 0, 0)), &vec!["ІП-82"], Some(last_modified()), 60);
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn if_none_match_takes_precedence() {
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"outdated\""))
+            .insert_header(IfModifiedSince(HttpDate::from(SystemTime::from(last_modified()))))
+            .to_http_request();
+
+        let response = cacheable_json(&req, &vec!["ІП-82"], Some(last_modified()), 60);
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
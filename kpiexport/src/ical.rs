@@ -0,0 +1,248 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use sha2::{Digest, Sha256};
+use crate::models::lesson_times::{LessonTime, LessonTimes};
+use crate::models::academic_calendar::{AcademicCalendar, AcademicTerm};
+use crate::models::schedule::{GroupSchedule, GroupScheduleEntry};
+
+const PRODUCT_ID: &str = "-//nikitavbv//kpiexport//UK";
+const TIMEZONE_ID: &str = "Europe/Kyiv";
+const UID_DOMAIN: &str = "kpiexport.nikitavbv.com";
+const MAX_LINE_LENGTH: usize = 75; // in octets, see RFC 5545 section 3.1
+
+// Ukraine follows the EU daylight saving rules: last Sunday of March and last Sunday of October.
+const VTIMEZONE: &[&str] = &[
+    "BEGIN:VTIMEZONE",
+    "TZID:Europe/Kyiv",
+    "BEGIN:DAYLIGHT",
+    "TZOFFSETFROM:+0200",
+    "TZOFFSETTO:+0300",
+    "TZNAME:EEST",
+    "DTSTART:19700329T030000",
+    "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU",
+    "END:DAYLIGHT",
+    "BEGIN:STANDARD",
+    "TZOFFSETFROM:+0300",
+    "TZOFFSETTO:+0200",
+    "TZNAME:EET",
+    "DTSTART:19701025T040000",
+    "RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU",
+    "END:STANDARD",
+    "END:VTIMEZONE",
+];
+
+// render group schedule as RFC 5545 calendar
+pub fn group_schedule_to_ical(group_name: &str, schedule: &GroupSchedule, calendar: &AcademicCalendar, term: &AcademicTerm, lesson_times: &LessonTimes, now: DateTime<Utc>) -> String {
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VCALENDAR".into(),
+        "VERSION:2.0".into(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "CALSCALE:GREGORIAN".into(),
+        "METHOD:PUBLISH".into(),
+        format!("X-WR-CALNAME:{}", escape_text(group_name)),
+        format!("X-WR-TIMEZONE:{}", TIMEZONE_ID),
+    ];
+    lines.extend(VTIMEZONE.iter().map(|v| v.to_string()));
+
+    for entry in &schedule.entries {
+        let lesson_time = match lesson_times.for_index(entry.index) {
+            Some(v) => v,
+            None => {
+                warn!("no lesson time for lesson index {}, skipping it in calendar", entry.index);
+                continue;
+            }
+        };
+
+        let first_lesson_date = match first_lesson_date(term, entry) {
+            Some(v) => v,
+            None => {
+                warn!("lesson does not happen during the term, skipping it in calendar");
+                continue;
+            }
+        };
+
+        lines.append(&mut event_lines(group_name, entry, first_lesson_date, lesson_time, calendar, term, now));
+    }
+
+    lines.push("END:VCALENDAR".into());
+
+    lines.iter()
+        .map(|v| fold_line(v))
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+#[allow(clippy::too_many_arguments)]
+fn event_lines(
+    group_name: &str,
+    entry: &GroupScheduleEntry,
+    date: NaiveDate,
+    lesson_time: &LessonTime,
+    calendar: &AcademicCalendar,
+    term: &AcademicTerm,
+    now: DateTime<Utc>
+) -> Vec<String> {
+    let summary = entry.names.join(" | ");
+
+    let mut lines: Vec<String> = vec![
+        "BEGIN:VEVENT".into(),
+        format!("UID:{}@{}", event_uid(group_name, entry), UID_DOMAIN),
+        format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")),
+        format!("DTSTART;TZID={}:{}", TIMEZONE_ID, date.and_time(lesson_time.start).format("%Y%m%dT%H%M%S")),
+        format!("DTEND;TZID={}:{}", TIMEZONE_ID, date.and_time(lesson_time.end).format("%Y%m%dT%H%M%S")),
+        format!("RRULE:FREQ=WEEKLY;INTERVAL=2;WKST=MO;UNTIL={}T235959Z", term.ends_on.format("%Y%m%d")),
+    ];
+
+    let mut occurrence = date;
+    while occurrence <= term.ends_on {
+        if calendar.exclusion_at(occurrence).is_some() {
+            lines.push(format!("EXDATE;TZID={}:{}", TIMEZONE_ID, occurrence.and_time(lesson_time.start).format("%Y%m%dT%H%M%S")));
+        }
+
+        occurrence += Duration::days(14);
+    }
+
+    lines.append(&mut vec![
+        format!("SUMMARY:{}", escape_text(&summary)),
+        format!("DESCRIPTION:{}", escape_text(&format!("{}\nВикладач: {}", summary, entry.lecturers.join(" | ")))),
+        format!("LOCATION:{}", escape_text(&entry.locations.join(" | "))),
+        "END:VEVENT".into(),
+    ]);
+
+    lines
+}
+
+// Calendar clients match events of a refreshed calendar by uid, so it only depends on the lesson itself and
+// not on what else is in the schedule. Names are hashed to keep the uid short.
+fn event_uid(group_name: &str, entry: &GroupScheduleEntry) -> String {
+    let names_hash = Sha256::digest(entry.names.join("\n").as_bytes());
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        escape_text(group_name),
+        entry.week.to_index(),
+        entry.day.to_index(),
+        entry.index,
+        names_hash.iter().take(8).map(|v| format!("{:02x}", v)).collect::<String>()
+    )
+}
+
+// first day on or after the term start matching both week parity and day of the entry
+fn first_lesson_date(term: &AcademicTerm, entry: &GroupScheduleEntry) -> Option<NaiveDate> {
+    (0..14)
+        .map(|offset| term.starts_on + Duration::days(offset))
+        .find(|date| date.weekday().num_days_from_monday() as u8 == entry.day.to_index() && term.week(*date) == entry.week)
+        .filter(|date| *date <= term.ends_on)
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// lines longer than 75 octets are split, continuation lines start with a single space.
+fn fold_line(line: &str) -> String {
+    let mut result = String::new();
+    let mut current_line_length = 0;
+
+    for c in line.chars() {
+        if current_line_length + c.len_utf8() > MAX_LINE_LENGTH {
+            result.push_str("\r\n ");
+            current_line_length = 1;
+        }
+
+        result.push(c);
+        current_line_length += c.len_utf8();
+    }
+
+    result.push_str("\r\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::academic_calendar::{CalendarExclusion, ExclusionKind};
+    use crate::models::schedule::{GroupScheduleSource, ScheduleDay, ScheduleWeek};
+
+    fn test_schedule() -> GroupSchedule {
+        GroupSchedule {
+            entries: vec![
+                GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 0)
+                    .with_names(vec!["Математичний аналіз".into()])
+                    .with_lecturers(vec!["доц. Іваненко І. І.".into()])
+                    .with_locations(vec!["7-418 Лек".into()]),
+                GroupScheduleEntry::new(ScheduleWeek::Second, ScheduleDay::Friday, 2)
+                    .with_names(vec!["Фізика; лабораторна".into()]),
+            ],
+            source: Some(GroupScheduleSource::Parser),
+            updated_at: None,
+            stale: false,
+            cancelled_entries: Vec::new(),
+            exceptions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn calendar_contains_biweekly_events() {
+        let academic_calendar = AcademicCalendar::new(vec![], vec![
+            CalendarExclusion {
+                starts_on: NaiveDate::from_ymd(2021, 10, 4),
+                ends_on: NaiveDate::from_ymd(2021, 10, 8),
+                kind: ExclusionKind::Holiday,
+                description: "Осінні канікули".into(),
+            }
+        ]);
+        let term = AcademicTerm::estimated(NaiveDate::from_ymd(2021, 9, 1));
+        let calendar = group_schedule_to_ical("ІП-82", &test_schedule(), &academic_calendar, &term, &LessonTimes::standard(), Utc::now());
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(calendar.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Kyiv\r\n"));
+        // September 1st 2021 is Wednesday, so first Monday lesson happens two weeks later
+        assert!(calendar.contains("DTSTART;TZID=Europe/Kyiv:20210913T083000\r\n"));
+        assert!(calendar.contains("DTEND;TZID=Europe/Kyiv:20210913T101000\r\n"));
+        assert!(calendar.contains("DTSTART;TZID=Europe/Kyiv:20210910T122000\r\n"));
+        assert_eq!(calendar.matches("RRULE:FREQ=WEEKLY;INTERVAL=2;WKST=MO;UNTIL=20211231T235959Z\r\n").count(), 2);
+        assert!(calendar.contains("SUMMARY:Фізика\\; лабораторна\r\n"));
+        // only friday lesson happens during holidays
+        assert_eq!(calendar.matches("EXDATE").count(), 1);
+        assert!(calendar.contains("EXDATE;TZID=Europe/Kyiv:20211008T122000\r\n"));
+    }
+
+    #[test]
+    fn event_uids_do_not_depend_on_other_lessons() {
+        let term = AcademicTerm::estimated(NaiveDate::from_ymd(2021, 9, 1));
+        let uids = |schedule: &GroupSchedule| -> Vec<String> {
+            group_schedule_to_ical("ІП-82", schedule, &AcademicCalendar::default(), &term, &LessonTimes::standard(), Utc::now())
+                .split("\r\n")
+                .filter(|v| v.starts_with("UID:"))
+                .map(String::from)
+                .collect()
+        };
+
+        let schedule = test_schedule();
+        let mut without_first_lesson = test_schedule();
+        without_first_lesson.entries.remove(0);
+        let mut renamed = test_schedule();
+        renamed.entries[1].names = vec!["Фізика".into()];
+
+        let uids_before = uids(&schedule);
+        assert_eq!(uids_before.len(), 2);
+        assert!(uids_before[1].starts_with("UID:ІП-82-1-4-2-"));
+        assert!(uids_before[1].ends_with("@kpiexport.nikitavbv.com"));
+        assert_eq!(uids(&without_first_lesson), vec![uids_before[1].clone()]);
+        assert_ne!(uids(&renamed)[1], uids_before[1]);
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let line = "DESCRIPTION:".to_string() + &"ї".repeat(100);
+        let folded = fold_line(&line);
+
+        assert!(folded.split("\r\n").all(|v| v.len() <= MAX_LINE_LENGTH));
+        assert_eq!(folded.replace("\r\n ", "").trim_end(), line);
+    }
+}
@@ -0,0 +1,83 @@
+use std::env::var;
+use std::io::{Result as IOResult, ErrorKind};
+use std::io::Error as IOError;
+
+use crate::custom::importer::read_schedule_xlsx;
+use crate::database::database_connection;
+use crate::models::lecturers::index_lecturers;
+use crate::schedule_updates::save_group_schedule;
+
+// KPIEXPORT_IMPORT_XLSX is the path of the file, see custom::importer for its layout. Either all groups from the
+// file are saved or none of them.
+pub async fn import_xlsx() -> IOResult<()> {
+    let path = var("KPIEXPORT_IMPORT_XLSX").unwrap_or_default();
+    if path.is_empty() {
+        return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            "KPIEXPORT_IMPORT_XLSX should be set to path of the xlsx file"
+        ));
+    }
+
+    let report = match read_schedule_xlsx(&path) {
+        Ok(v) => v,
+        Err(err) => return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            format!("failed to import {}: {}", path, err)
+        ))
+    };
+
+    if !report.errors.is_empty() {
+        for err in &report.errors {
+            error!("{}", err);
+        }
+
+        return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            format!("{} has {} invalid rows, nothing was imported", path, report.errors.len())
+        ));
+    }
+
+    let mut database = match database_connection().await {
+        Ok(v) => v,
+        Err(err) => return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            format!("failed to connect to database: {}", err)
+        ))
+    };
+
+    let mut transaction = match database.transaction().await {
+        Ok(v) => v,
+        Err(err) => return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            format!("failed to start transaction: {}", err)
+        ))
+    };
+
+    for (group_name, schedule) in &report.schedules {
+        let changes = match save_group_schedule(&mut transaction, group_name, schedule).await {
+            Ok(v) => v,
+            Err(err) => return IOResult::Err(IOError::new(
+                ErrorKind::Other,
+                format!("failed to save schedule of {}: {}", group_name, err)
+            ))
+        };
+
+        info!("imported {} lessons for {}, {} changes", schedule.entries.len(), group_name, changes.len());
+    }
+
+    if let Err(err) = transaction.commit().await {
+        return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            format!("failed to commit imported schedules: {}", err)
+        ));
+    }
+
+    info!("imported schedule for {} groups from {}", report.schedules.len(), path);
+
+    match index_lecturers(&database).await {
+        Ok(v) => info!("added {} new lecturers", v),
+        Err(err) => error!("failed to index lecturers: {}", err),
+    }
+
+    Ok(())
+}
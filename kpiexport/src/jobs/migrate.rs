@@ -0,0 +1,31 @@
+use std::io::{Result as IOResult, ErrorKind};
+use std::io::Error as IOError;
+
+use crate::database::database_connection;
+
+refinery::embed_migrations!("migrations");
+
+pub async fn migrate() -> IOResult<()> {
+    let mut database = match database_connection().await {
+        Ok(v) => v,
+        Err(err) => return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            format!("failed to connect to database: {}", err)
+        ))
+    };
+
+    let report = match migrations::runner().run_async(&mut database).await {
+        Ok(v) => v,
+        Err(err) => return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            format!("failed to apply migrations: {}", err)
+        ))
+    };
+
+    for migration in report.applied_migrations() {
+        info!("applied migration {}", migration);
+    }
+    info!("database is up to date, applied {} migrations", report.applied_migrations().len());
+
+    Ok(())
+}
@@ -1,2 +1,3 @@
 pub mod refresh_groups;
-pub mod refresh_schedule;
\ No newline at end of file
+pub mod refresh_schedule;pub mod migrate;
+pub mod import_xlsx;
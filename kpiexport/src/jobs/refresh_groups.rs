@@ -1,11 +1,13 @@
 use std::io::{Result as IOResult, ErrorKind};
 use std::io::Error as IOError;
 
-use chrono::prelude::*;
+use chrono::Utc;
 
 use crate::database::database_connection;
-use crate::rozklad_parser::get_groups;
+use crate::models::academic_calendar::load_academic_calendar;
+use crate::rozklad::ScheduleSources;
 use crate::models::groups::{delete_all_groups_transaction, add_group_transaction, total_old_groups};
+use crate::timetable::kyiv_date;
 
 pub async fn refresh_groups() -> IOResult<()> {
     let client = reqwest::Client::new();
@@ -17,10 +19,16 @@ pub async fn refresh_groups() -> IOResult<()> {
         ))
     };
 
-    // in January, February, August and September refresh every 2-3 days, other months - every ~20 days
-    let utc: DateTime<Utc> = Utc::now();
-    let is_hot_month = utc.month0() <= 1 || utc.month0() == 7 || utc.month0() == 8;
-    let days_diff = if is_hot_month { 2 } else { 20 };
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            format!("failed to load academic calendar: {}", err)
+        ))
+    };
+
+    // around the start of the term refresh every 2-3 days, otherwise - every ~20 days
+    let days_diff = if calendar.is_near_term_start(kyiv_date(Utc::now())) { 2 } else { 20 };
 
     let old_groups = match total_old_groups(&database, days_diff).await {
         Ok(v) => v,
@@ -34,8 +42,8 @@ pub async fn refresh_groups() -> IOResult<()> {
         return Ok(())
     }
 
-    let groups = get_groups(&client).await;
-    info!("got {} groups from parser", groups.len());
+    let groups = ScheduleSources::from_config().groups(&client).await;
+    info!("got {} groups from schedule sources", groups.len());
     if groups.len() > 0 {
         let transaction = database.transaction().await
             .expect("failed to start transaction");
@@ -1,13 +1,17 @@
 use std::io::{Result as IOResult, ErrorKind};
 use std::io::Error as IOError;
 
-use chrono::{DateTime, Utc, Datelike};
-use tokio_postgres::{Client, Transaction};
+use chrono::Utc;
+use tokio_postgres::Client;
 
 use crate::database::database_connection;
-use crate::models::schedule_queries::{api_groups_to_refresh, groups_with_old_schedule, remove_old_schedule_from_database_transaction, save_schedule_to_database_transaction};
-use crate::rozklad::group_schedule_by_name;
-use crate::rozklad_parser::Term;
+use crate::models::schedule_queries::{api_groups_to_refresh, groups_with_old_schedule};
+use crate::rozklad::ScheduleSources;
+use crate::models::academic_calendar::{load_academic_calendar, Term};
+use crate::models::lecturers::index_lecturers;
+use crate::schedule_updates::save_group_schedule;
+use crate::webhooks::deliver_due_webhooks;
+use crate::timetable::kyiv_date;
 
 pub async fn refresh_schedule() -> IOResult<()> {
     let client = reqwest::Client::new();
@@ -19,10 +23,18 @@ pub async fn refresh_schedule() -> IOResult<()> {
         ))
     };
 
-    // in January, February, August and September refresh every ~6 hours, other months - every ~500 hours (around once a month)
-    let utc: DateTime<Utc> = Utc::now();
-    let is_hot_month = utc.month0() <= 1 || utc.month0() == 7 || utc.month0() == 8;
-    let hours_diff = if is_hot_month { 6 } else { 500 };
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => return IOResult::Err(IOError::new(
+            ErrorKind::Other,
+            format!("failed to load academic calendar: {}", err)
+        ))
+    };
+
+    // around the start of the term refresh every ~6 hours, otherwise - every ~500 hours (around once a month)
+    let today = kyiv_date(Utc::now());
+    let term = calendar.term_at(today).term;
+    let hours_diff = if calendar.is_near_term_start(today) { 6 } else { 500 };
 
     let groups_with_api_source = match api_groups_to_refresh(&database).await {
         Ok(v) => v,
@@ -34,7 +46,7 @@ pub async fn refresh_schedule() -> IOResult<()> {
 
     if groups_with_api_source.len() > 0 {
         info!("refreshing groups with api source schedule: {}", groups_with_api_source.len());
-        return refresh_schedule_for_groups(database, client, &groups_with_api_source).await;
+        return refresh_schedule_for_groups(database, client, &term, &groups_with_api_source).await;
     }
 
     let groups_to_refresh = match groups_with_old_schedule(&database, hours_diff).await {
@@ -47,38 +59,46 @@ pub async fn refresh_schedule() -> IOResult<()> {
 
     if groups_to_refresh.len() > 0 {
         info!("refreshing groups with old schedule: {}", groups_to_refresh.len());
-        return refresh_schedule_for_groups(database, client, &groups_to_refresh).await;
+        return refresh_schedule_for_groups(database, client, &term, &groups_to_refresh).await;
     }
 
     info!("looks like there is nothing to refresh");
     Ok(())
 }
 
-async fn refresh_schedule_for_groups(database: Client, client: reqwest::Client, groups_to_refresh: &Vec<String>) -> IOResult<()> {
+async fn refresh_schedule_for_groups(database: Client, client: reqwest::Client, term: &Term, groups_to_refresh: &Vec<String>) -> IOResult<()> {
     let mut database = database;
+    let sources = ScheduleSources::from_config();
 
     for group_to_refresh in groups_to_refresh {
         info!("refreshing schedule for {}", group_to_refresh);
+        refresh_schedule_for_group(&mut database, &client, &sources, term, group_to_refresh).await?;
+    }
 
-        let transaction = database.transaction().await
-            .expect("failed to start transaction");
-
-        refresh_schedule_for_group(&transaction, &client,group_to_refresh).await?;
+    info!("refreshed schedule for {} groups", groups_to_refresh.len());
 
-        transaction.commit().await.expect("failed to commit transaction");
+    match index_lecturers(&database).await {
+        Ok(v) => info!("added {} new lecturers", v),
+        Err(err) => error!("failed to index lecturers: {}", err),
     }
 
-    info!("refreshed schedule for {} groups", groups_to_refresh.len());
+    // failed ones are retried by web servers
+    match deliver_due_webhooks(&database).await {
+        Ok(v) => info!("made {} webhook delivery attempts", v),
+        Err(err) => error!("failed to deliver webhooks: {}", err),
+    }
 
     Ok(())
 }
 
-async fn refresh_schedule_for_group(database: &Transaction<'_>, client: &reqwest::Client, group_name: &str) -> IOResult<()> {
-    if let Err(err) = remove_old_schedule_from_database_transaction(&database, &group_name).await {
-        error!("failed to remove schedule from database: {}", err);
-    }
-
-    let schedule = match group_schedule_by_name(&client, &Term::current(), &group_name).await {
+async fn refresh_schedule_for_group(
+    database: &mut Client,
+    client: &reqwest::Client,
+    sources: &ScheduleSources,
+    term: &Term,
+    group_name: &str
+) -> IOResult<()> {
+    let schedule = match sources.group_schedule(client, term, group_name).await {
         Ok(v) => v,
         Err(err) => {
             error!("failed to get group schedule: {}", err);
@@ -89,14 +109,15 @@ async fn refresh_schedule_for_group(database: &Transaction<'_>, client: &reqwest
         }
     };
 
-    if let Err(err) = save_schedule_to_database_transaction(&database, &group_name, &schedule).await {
-        return IOResult::Err(IOError::new(
+    let changes = match save_group_schedule(database, group_name, &schedule).await {
+        Ok(v) => v,
+        Err(err) => return IOResult::Err(IOError::new(
             ErrorKind::Other,
             format!("failed to save group schedule to database {}", err)
-        ));
-    }
+        )),
+    };
 
-    info!("refreshed schedule for {}", group_name);
+    info!("refreshed schedule for {}, {} changes", group_name, changes.len());
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,66 @@
+use crate::merged_schedule::{merge_group_entries, MergedScheduleEntry};
+use crate::models::schedule::GroupScheduleEntry;
+
+pub fn merge_lecturer_schedule(lecturer_name: &str, group_entries: Vec<(String, GroupScheduleEntry)>) -> Vec<MergedScheduleEntry> {
+    merge_group_entries(group_entries.into_iter()
+        .map(|(group_name, entry)| (group_name, only_lecturer_lessons(lecturer_name, entry)))
+        .collect()
+    )
+}
+
+// split lessons have a lecturer for each name, only names taught by the lecturer are kept
+fn only_lecturer_lessons(lecturer_name: &str, entry: GroupScheduleEntry) -> GroupScheduleEntry {
+    if entry.names.len() < 2 || entry.lecturers.len() != entry.names.len() {
+        return entry;
+    }
+
+    let taught: Vec<usize> = entry.lecturers.iter()
+        .enumerate()
+        .filter(|(_, name)| name.trim() == lecturer_name)
+        .map(|(index, _)| index)
+        .collect();
+
+    entry.with_only_names(&taught)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schedule::{ScheduleDay, ScheduleWeek};
+
+    #[test]
+    fn co_taught_lessons_are_merged() {
+        let lecture = || GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Tuesday, 1)
+            .with_names(vec!["Бази даних".into()])
+            .with_lecturers(vec!["Іваненко І. І.".into()])
+            .with_locations(vec!["7-418".into()]);
+
+        let schedule = merge_lecturer_schedule("Іваненко І. І.", vec![
+            ("ІП-82".into(), lecture()),
+            ("ІП-81".into(), lecture()),
+            ("ІП-81".into(), GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 3)
+                .with_names(vec!["Бази даних".into()])
+                .with_lecturers(vec!["Іваненко І. І.".into()])),
+        ]);
+
+        assert_eq!(schedule.len(), 2);
+        assert_eq!(schedule[0].entry.day, ScheduleDay::Monday);
+        assert_eq!(schedule[0].groups, vec!["ІП-81".to_string()]);
+        assert_eq!(schedule[1].groups, vec!["ІП-81".to_string(), "ІП-82".to_string()]);
+        assert_eq!(schedule[1].entry.locations, vec!["7-418".to_string()]);
+    }
+
+    #[test]
+    fn split_lessons_keep_only_lecturer_part() {
+        let schedule = merge_lecturer_schedule("Петренко П. П.", vec![
+            ("ІП-82".into(), GroupScheduleEntry::new(ScheduleWeek::Second, ScheduleDay::Friday, 0)
+                .with_names(vec!["Англійська мова (група 1)".into(), "Англійська мова (група 2)".into()])
+                .with_lecturers(vec!["Іваненко І. І.".into(), "Петренко П. П.".into()])
+                .with_locations(vec!["7-301".into(), "7-302".into()])),
+        ]);
+
+        assert_eq!(schedule[0].entry.names, vec!["Англійська мова (група 2)".to_string()]);
+        assert_eq!(schedule[0].entry.lecturers, vec!["Петренко П. П.".to_string()]);
+        assert_eq!(schedule[0].entry.locations, vec!["7-302".to_string()]);
+    }
+}
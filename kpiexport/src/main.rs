@@ -4,180 +4,1117 @@
 extern crate custom_error;
 
 use std::env;
-use rozklad::group_schedule_by_name;
-use actix_web::{App, HttpServer, Responder, get, HttpResponse, web };
-use config::bind_address;
-use prometheus::{TextEncoder, Encoder, Counter, register_counter, opts};
-use database::database_connection;
-use errors::PersistenceError;
-use models::schedule::{GroupScheduleSource, GroupSchedule, GroupScheduleEntry, ScheduleWeek, ScheduleDay};
+use std::convert::TryFrom;
+use rozklad::ScheduleSources;
+use actix_web::{App, HttpServer, HttpRequest, Responder, get, post, delete, HttpResponse, web };
+use actix_web::http::header;
+use config::{admin_token, bind_address, schedule_cache_capacity, schedule_cache_ttl, schedule_max_age, stale_schedule_refresh_interval};
+use prometheus::{TextEncoder, Encoder, Counter, IntGauge, register_counter, register_int_gauge, opts};
+use database::{database_pool, listen_for_notifications, DatabaseError, DatabasePool};
+use deadpool_postgres::PoolError;
+use errors::RozkladParseError;
+use models::schedule::{GroupSchedule, GroupScheduleEntry, ScheduleException};
 use git_version::git_version;
-use chrono::Duration;
-use crate::models::groups::{total_groups_saved, add_group};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use chrono::{DateTime, Utc};
+use crate::models::groups::{total_groups_saved, add_group, all_groups, groups_updated_at};
+use crate::models::academic_calendar::{load_academic_calendar, AcademicCalendar, Term};
+use crate::models::lesson_times::{lesson_times_for_academic_year, LessonTimes};
+use crate::matching::{rank_group_names, resolve_group_name, GroupMatch};
+use crate::models::students::student_subjects_for_group;
+use crate::personal_schedule::{find_student, schedule_for_student, StudentLookupError};
+use crate::timetable::{current_lesson, kyiv_date, next_lesson, ScheduledLesson};
 use crate::jobs::refresh_groups::refresh_groups;
 use crate::jobs::refresh_schedule::refresh_schedule;
-use crate::models::schedule_queries::{remove_old_schedule_from_database, save_schedule_to_database};
-use crate::rozklad_parser::Term;
+use crate::jobs::migrate::migrate;
+use crate::jobs::import_xlsx::import_xlsx;
+use crate::models::schedule_queries::{load_group_schedule_from_database, SCHEDULE_UPDATES_CHANNEL};
+use crate::schedule_updates::save_group_schedule;
+use crate::models::lecturers::{add_lecturers, all_lecturers, lecturer_by_id, lecturer_schedule_entries, schedule_lecturers, Lecturer};
+use crate::matching::person_name_contains;
+use crate::lecturer_schedule::merge_lecturer_schedule;
+use crate::merged_schedule::MergedScheduleEntry;
+use crate::models::rooms::{all_locations, locations_at, room_schedule_entries};
+use crate::rooms::{free_rooms, parse_location, room_schedule, Room};
+use crate::schedule_fetches::GroupScheduleFetches;
+use crate::schedule_cache::ScheduleCache;
+use crate::http_cache::cacheable_json;
+use crate::models::webhooks::{add_webhook_subscription, all_webhook_subscriptions, remove_webhook_subscription, WebhookSubscription};
+use crate::webhooks::{deliver_due_webhooks, is_allowed_webhook_url, new_webhook_secret};
+use crate::models::schedule_changes::{schedule_changes_since, RecordedScheduleChange};
+use crate::ical::group_schedule_to_ical;
+use crate::models::schedule_overrides::{active_schedule_overrides, add_schedule_override, remove_schedule_override, schedule_overrides_for_group, NewScheduleOverride};
+use crate::models::schedule_queries::notify_schedule_updated;
+use crate::schedule_overrides::apply_schedule_overrides;
+use crate::utils::constant_time_eq;
 
 mod config;
 mod custom;
 mod database;
 mod e2e;
 mod errors;
+mod ical;
 mod models;
 mod rozklad;
 mod rozklad_parser;
 mod rozklad_api;
+mod campus_api;
+mod timetable;
 mod utils;
 mod jobs;
+mod matching;
+mod personal_schedule;
+mod lecturer_schedule;
+mod merged_schedule;
+mod rooms;
+mod single_flight;
+mod schedule_cache;
+mod http_cache;
+mod schedule_diff;
+mod webhooks;
+mod schedule_updates;
+mod schedule_fetches;
+mod schedule_source;
+mod circuit_breaker;
+mod schedule_overrides;
+#[cfg(test)]
+mod rozklad_mock;
+#[cfg(test)]
+mod test_fixtures;
 
 const VERSION: &str = git_version!();
 
+const GROUP_SEARCH_RESULTS: usize = 10;
+const LECTURER_SEARCH_RESULTS: usize = 20;
+// how long clients and CDNs can use responses without revalidating them
+const GROUPS_MAX_AGE_SECONDS: u32 = 3600;
+const GROUP_SCHEDULE_MAX_AGE_SECONDS: u32 = 300;
+const STALE_GROUP_SCHEDULE_MAX_AGE_SECONDS: u32 = 60;
+const SCHEDULE_CHANGES_DEFAULT_DAYS: i64 = 7;
+const WEBHOOK_DELIVERY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
 lazy_static! {
     static ref GROUPS_LIST_REQUESTS: Counter = register_counter!(opts!(
         "kpiexport_requests_groups",
         "Total group list requests"
     )).unwrap();
+    static ref GROUP_SEARCH_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_group_search",
+        "Total group search requests"
+    )).unwrap();
+    static ref COALESCED_SCHEDULE_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_coalesced_schedule_fetches",
+        "Total requests which waited for a schedule fetch started by another request for the same group"
+    )).unwrap();
     static ref GROUP_SCHEDULE_REQUESTS: Counter = register_counter!(opts!(
         "kpiexport_requests_group_schedule",
         "Total group schedule requests"
     )).unwrap();
+    static ref SCHEDULE_CACHE_HITS: Counter = register_counter!(opts!(
+        "kpiexport_requests_group_schedule_cache_hits",
+        "Total group schedule requests served from the in-memory cache"
+    )).unwrap();
+    static ref SCHEDULE_CACHE_MISSES: Counter = register_counter!(opts!(
+        "kpiexport_requests_group_schedule_cache_misses",
+        "Total group schedule requests not found in the in-memory cache"
+    )).unwrap();
+    static ref STALE_SCHEDULE_RESPONSES: Counter = register_counter!(opts!(
+        "kpiexport_requests_stale_schedule",
+        "Total requests served with a stored schedule older than the max age"
+    )).unwrap();
+    static ref SCHEDULE_CHANGES_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_schedule_changes",
+        "Total group schedule changes requests"
+    )).unwrap();
+    static ref WEBHOOK_SUBSCRIPTION_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_webhook_subscriptions",
+        "Total webhook subscription management requests"
+    )).unwrap();
+    static ref SCHEDULE_OVERRIDE_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_schedule_overrides",
+        "Total schedule override management requests"
+    )).unwrap();
+    static ref PERSONAL_SCHEDULE_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_personal_schedule",
+        "Total personal schedule requests"
+    )).unwrap();
+    static ref GROUP_CALENDAR_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_group_calendar",
+        "Total group calendar (.ics) requests"
+    )).unwrap();
+    static ref GROUP_LESSON_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_group_lesson",
+        "Total current or next lesson requests"
+    )).unwrap();
+    static ref LECTURER_SEARCH_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_lecturer_search",
+        "Total lecturer search requests"
+    )).unwrap();
+    static ref LECTURER_SCHEDULE_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_lecturer_schedule",
+        "Total lecturer schedule requests"
+    )).unwrap();
+    static ref DATABASE_POOL_TIMEOUTS: Counter = register_counter!(opts!(
+        "kpiexport_database_pool_timeouts",
+        "Total requests rejected because no database connection was available in time"
+    )).unwrap();
+    static ref DATABASE_POOL_MAX_SIZE: IntGauge = register_int_gauge!(opts!(
+        "kpiexport_database_pool_max_size",
+        "Maximum number of database connections in the pool"
+    )).unwrap();
+    static ref DATABASE_POOL_SIZE: IntGauge = register_int_gauge!(opts!(
+        "kpiexport_database_pool_size",
+        "Current number of database connections in the pool"
+    )).unwrap();
+    static ref DATABASE_POOL_AVAILABLE: IntGauge = register_int_gauge!(opts!(
+        "kpiexport_database_pool_available",
+        "Idle database connections in the pool, negative when requests are waiting for a connection"
+    )).unwrap();
+    static ref ROOM_SCHEDULE_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_room_schedule",
+        "Total room schedule requests"
+    )).unwrap();
+    static ref FREE_ROOMS_REQUESTS: Counter = register_counter!(opts!(
+        "kpiexport_requests_free_rooms",
+        "Total free room search requests"
+    )).unwrap();
+}
+
+#[derive(Deserialize)]
+struct GroupName {
+    group_name: String,
+}
+
+#[derive(Deserialize)]
+struct StudentLastName {
+    #[serde(rename = "lastName")]
+    last_name: String,
+}
+
+#[derive(Deserialize)]
+struct LessonAtQuery {
+    at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct GroupSearchQuery {
+    q: String,
+}
+
+#[derive(Deserialize)]
+struct LecturerSearchQuery {
+    q: String,
+}
+
+#[derive(Deserialize)]
+struct ScheduleChangesQuery {
+    since: Option<DateTime<Utc>>, // last week when not set
+}
+
+#[derive(Serialize)]
+struct ScheduleChangesResponse {
+    group_name: String,
+    changes: Vec<RecordedScheduleChange>,
+}
+
+#[derive(Deserialize)]
+struct NewWebhookSubscription {
+    url: String,
+    #[serde(default)]
+    group_names: Vec<String>, // every group when empty
+}
+
+#[derive(Serialize)]
+struct CreatedWebhookSubscriptionResponse {
+    #[serde(flatten)]
+    subscription: WebhookSubscription,
+    secret: String, // for checking payload signatures, not returned again
+}
+
+#[derive(Deserialize)]
+struct WebhookSubscriptionId {
+    subscription_id: i32,
+}
+
+#[derive(Deserialize)]
+struct ScheduleOverrideId {
+    group_name: String,
+    override_id: i32,
+}
+
+#[derive(Serialize)]
+struct LecturerScheduleResponse {
+    lecturer: Lecturer,
+    entries: Vec<MergedScheduleEntry>,
+}
+
+#[derive(Deserialize)]
+struct RoomName {
+    room_name: String,
+}
+
+#[derive(Serialize)]
+struct RoomScheduleResponse {
+    room: Room,
+    entries: Vec<MergedScheduleEntry>,
+}
+
+// week, day and index are the same as in schedule entries: first week, monday and first lesson are 0
+#[derive(Deserialize)]
+struct FreeRoomsQuery {
+    week: i16,
+    day: i16,
+    index: i16,
+    building: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SubjectName {
+    subject_name: String,
+}
+
+#[derive(Serialize)]
+struct SubjectResponse {
+    link: String,
+    emoji: String,
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    env_logger::init();
+
+    let args: Vec<String> = env::vars().map(|v| v.0).collect();
+    let contains_refresh_groups = args.contains(&"KPIEXPORT_REFRESH_GROUPS_JOB".to_string());
+    let contains_refresh_schedule = args.contains(&"KPIEXPORT_REFRESH_SCHEDULE_JOB".to_string());
+    let contains_migrate = args.contains(&"KPIEXPORT_MIGRATE".to_string());
+    let contains_import_xlsx = args.contains(&"KPIEXPORT_IMPORT_XLSX".to_string());
+
+    if contains_migrate {
+        println!("applying database migrations");
+        migrate().await
+    } else if contains_refresh_groups {
+        println!("starting refresh groups job");
+        refresh_groups().await
+    } else if contains_refresh_schedule {
+        println!("starting refresh schedule job");
+        refresh_schedule().await
+    } else if contains_import_xlsx {
+        println!("starting import xlsx job");
+        import_xlsx().await
+    } else {
+        info!("starting kpiexport webserver");
+        start_webserver().await
+    }
+}
+
+async fn start_webserver() -> std::io::Result<()> {
+    let database_pool = match database_pool() {
+        Ok(v) => web::Data::new(v),
+        Err(err) => return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("failed to create database pool: {}", err)
+        )),
+    };
+
+    let schedule_fetches: web::Data<GroupScheduleFetches> = web::Data::new(GroupScheduleFetches::new(stale_schedule_refresh_interval(), ScheduleSources::from_config()));
+    let schedule_cache = web::Data::new(ScheduleCache::new(schedule_cache_capacity(), schedule_cache_ttl()));
+
+    actix_rt::spawn(invalidate_schedule_cache_on_updates(schedule_cache.clone()));
+    actix_rt::spawn(deliver_webhooks_periodically(database_pool.clone()));
+
+    HttpServer::new(move || App::new()
+        .app_data(database_pool.clone())
+        .app_data(schedule_fetches.clone())
+        .app_data(schedule_cache.clone())
+        .service(healthz)
+        .service(metrics)
+        .service(service_version)
+        .service(groups)
+        .service(group_search)
+        .service(group_schedule)
+        .service(group_calendar)
+        .service(schedule_changes)
+        .service(webhook_subscriptions)
+        .service(create_webhook_subscription)
+        .service(delete_webhook_subscription)
+        .service(group_schedule_overrides)
+        .service(create_schedule_override)
+        .service(delete_schedule_override)
+        .service(student_schedule)
+        .service(group_lesson_now)
+        .service(group_lesson_next)
+        .service(lecturer_search)
+        .service(lecturer_schedule_by_id)
+        .service(free_rooms_at)
+        .service(room_schedule_by_name)
+        .service(subject_id_by_name)
+        .service(subject_info_by_id)
+    )
+        .bind(bind_address())?
+        .run()
+        .await
+}
+
+// refresh jobs run in separate processes, they notify web servers about saved schedules through postgres
+async fn invalidate_schedule_cache_on_updates(schedule_cache: web::Data<ScheduleCache>) {
+    loop {
+        // updates could have been missed while there was no connection
+        schedule_cache.clear();
+
+        let result = listen_for_notifications(SCHEDULE_UPDATES_CHANNEL, |group_name| {
+            info!("schedule updated for {}, removing it from cache", group_name);
+            schedule_cache.invalidate_group(group_name);
+        }).await;
+
+        match result {
+            Ok(_) => warn!("schedule updates connection closed, reconnecting"),
+            Err(err) => error!("failed to listen for schedule updates: {}", err),
+        }
+
+        actix_rt::time::sleep(std::time::Duration::from_secs(5)).await;
+    }
 }
 
-#[derive(Deserialize)]
-struct GroupName {
-    group_name: String,
+// retries deliveries which failed in refresh jobs and sends the ones enqueued by web servers
+async fn deliver_webhooks_periodically(database_pool: web::Data<DatabasePool>) {
+    loop {
+        actix_rt::time::sleep(WEBHOOK_DELIVERY_INTERVAL).await;
+
+        let database = match database_pool.get().await {
+            Ok(v) => v,
+            Err(err) => {
+                error!("failed to get database connection for webhook delivery: {}", err);
+                continue;
+            }
+        };
+
+        if let Err(err) = deliver_due_webhooks(&database).await {
+            error!("failed to deliver webhooks: {}", err);
+        }
+    }
+}
+
+#[get("/api/v1/groups/search")]
+async fn group_search(database_pool: web::Data<DatabasePool>, query: web::Query<GroupSearchQuery>) -> impl Responder {
+    info!("group search request");
+
+    GROUP_SEARCH_REQUESTS.inc();
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let known_groups = match all_groups(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to read groups from schedule_groups: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
+        }
+    };
+
+    let matches: Vec<GroupMatch> = rank_group_names(&query.q, &known_groups).into_iter()
+        .take(GROUP_SEARCH_RESULTS)
+        .collect();
+
+    HttpResponse::Ok().json(matches)
+}
+
+#[get("/groups/{group_name}")]
+async fn group_schedule(req: HttpRequest, database_pool: web::Data<DatabasePool>, schedule_fetches: web::Data<GroupScheduleFetches>, schedule_cache: web::Data<ScheduleCache>, group_name: web::Path<GroupName>) -> impl Responder {
+    info!("group schedule request");
+
+    GROUP_SCHEDULE_REQUESTS.inc();
+
+    let client = reqwest::Client::new();
+    let mut database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load academic calendar: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+    let today = kyiv_date(Utc::now());
+    let term = calendar.term_at(today);
+
+    // Responses only have etag and no Last-Modified. Besides the stored schedule they include overrides, subject
+    // ids and lesson times, which change on their own, and a removed override leaves no modification time.
+
+    // only known (or already fetched) groups get cached, so there is nothing to redirect
+    if let Some(schedule) = schedule_cache.get(&group_name.group_name, term.academic_year, term.term) {
+        SCHEDULE_CACHE_HITS.inc();
+        return cacheable_json(&req, &schedule, None, GROUP_SCHEDULE_MAX_AGE_SECONDS);
+    }
+    SCHEDULE_CACHE_MISSES.inc();
+    let cache_generation = schedule_cache.generation();
+
+    let known_groups = match all_groups(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to read groups from schedule_groups: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    if !known_groups.contains(&group_name.group_name) {
+        if let Some(canonical_name) = resolve_group_name(&group_name.group_name, &known_groups) {
+            info!("redirecting {} to {}", group_name.group_name, canonical_name);
+            return HttpResponse::Found()
+                .insert_header((header::LOCATION, format!("/groups/{}", utf8_percent_encode(&canonical_name, NON_ALPHANUMERIC))))
+                .finish();
+        }
+    }
+
+    let schedule = match load_or_fetch_group_schedule(&database_pool, &mut database, &client, &schedule_fetches, &term.term, &group_name.group_name).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to get group schedule: {}", err);
+            return HttpResponse::InternalServerError().body("failed to get group schedule");
+        }
+    };
+
+    // lessons of the next two weeks are shown, so are overrides for any day of them
+    let until = today + chrono::Duration::days(13);
+    let overrides = match active_schedule_overrides(&database, &group_name.group_name, today, until).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load schedule overrides: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+    let schedule = apply_schedule_overrides(schedule, &overrides, &calendar, today, until);
+
+    let lesson_times = match lesson_times_for_academic_year(&database, term.academic_year).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load lesson times: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    let schedule = match enrich_group_schedule(&database, schedule, &lesson_times).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to enrich group schedule: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    if schedule.stale {
+        // not cached, so that the next request gets the refreshed schedule as soon as it is saved
+        let mut response = cacheable_json(&req, &schedule, None, STALE_GROUP_SCHEDULE_MAX_AGE_SECONDS);
+        response.headers_mut().insert(header::WARNING, header::HeaderValue::from_static("110 - \"Response is Stale\""));
+        return response;
+    }
+
+    schedule_cache.insert(cache_generation, &group_name.group_name, term.academic_year, term.term, schedule.clone());
+
+    cacheable_json(&req, &schedule, None, GROUP_SCHEDULE_MAX_AGE_SECONDS)
+}
+
+#[get("/api/v1/groups/{group_name}/changes")]
+async fn schedule_changes(database_pool: web::Data<DatabasePool>, group_name: web::Path<GroupName>, query: web::Query<ScheduleChangesQuery>) -> impl Responder {
+    info!("schedule changes request");
+
+    SCHEDULE_CHANGES_REQUESTS.inc();
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let since = query.since.unwrap_or_else(|| Utc::now() - chrono::Duration::days(SCHEDULE_CHANGES_DEFAULT_DAYS));
+
+    let changes = match schedule_changes_since(&database, &group_name.group_name, &since).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to read schedule changes: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
+        }
+    };
+
+    HttpResponse::Ok().json(ScheduleChangesResponse {
+        group_name: group_name.group_name.clone(),
+        changes,
+    })
+}
+
+#[get("/api/v1/webhooks")]
+async fn webhook_subscriptions(req: HttpRequest, database_pool: web::Data<DatabasePool>) -> impl Responder {
+    info!("webhook subscriptions request");
+
+    WEBHOOK_SUBSCRIPTION_REQUESTS.inc();
+
+    if !is_admin_request(&req) {
+        return HttpResponse::Unauthorized().body("unauthorized");
+    }
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    match all_webhook_subscriptions(&database).await {
+        Ok(v) => HttpResponse::Ok().json(v),
+        Err(err) => {
+            error!("failed to read webhook subscriptions: {}", err);
+            HttpResponse::InternalServerError().body("internal_server_error")
+        }
+    }
+}
+
+#[post("/api/v1/webhooks")]
+async fn create_webhook_subscription(req: HttpRequest, database_pool: web::Data<DatabasePool>, subscription: web::Json<NewWebhookSubscription>) -> impl Responder {
+    info!("create webhook subscription request");
+
+    WEBHOOK_SUBSCRIPTION_REQUESTS.inc();
+
+    if !is_admin_request(&req) {
+        return HttpResponse::Unauthorized().body("unauthorized");
+    }
+
+    if !is_allowed_webhook_url(&subscription.url) {
+        return HttpResponse::BadRequest().body("invalid_url");
+    }
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let secret = new_webhook_secret();
+    match add_webhook_subscription(&database, &subscription.url, &subscription.group_names, &secret).await {
+        Ok(v) => HttpResponse::Created().json(CreatedWebhookSubscriptionResponse {
+            subscription: v,
+            secret,
+        }),
+        Err(err) => {
+            error!("failed to save webhook subscription: {}", err);
+            HttpResponse::InternalServerError().body("internal_server_error")
+        }
+    }
+}
+
+#[delete("/api/v1/webhooks/{subscription_id}")]
+async fn delete_webhook_subscription(req: HttpRequest, database_pool: web::Data<DatabasePool>, subscription: web::Path<WebhookSubscriptionId>) -> impl Responder {
+    info!("delete webhook subscription request");
+
+    WEBHOOK_SUBSCRIPTION_REQUESTS.inc();
+
+    if !is_admin_request(&req) {
+        return HttpResponse::Unauthorized().body("unauthorized");
+    }
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    match remove_webhook_subscription(&database, subscription.subscription_id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().body("webhook_subscription_not_found"),
+        Err(err) => {
+            error!("failed to remove webhook subscription: {}", err);
+            HttpResponse::InternalServerError().body("internal_server_error")
+        }
+    }
+}
+
+#[get("/api/v1/groups/{group_name}/overrides")]
+async fn group_schedule_overrides(req: HttpRequest, database_pool: web::Data<DatabasePool>, group_name: web::Path<GroupName>) -> impl Responder {
+    info!("schedule overrides request");
+
+    SCHEDULE_OVERRIDE_REQUESTS.inc();
+
+    if !is_admin_request(&req) {
+        return HttpResponse::Unauthorized().body("unauthorized");
+    }
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    match schedule_overrides_for_group(&database, &group_name.group_name).await {
+        Ok(v) => HttpResponse::Ok().json(v),
+        Err(err) => {
+            error!("failed to read schedule overrides: {}", err);
+            HttpResponse::InternalServerError().body("internal_server_error")
+        }
+    }
+}
+
+#[post("/api/v1/groups/{group_name}/overrides")]
+async fn create_schedule_override(req: HttpRequest, database_pool: web::Data<DatabasePool>, group_name: web::Path<GroupName>, new_override: web::Json<NewScheduleOverride>) -> impl Responder {
+    info!("create schedule override request");
+
+    SCHEDULE_OVERRIDE_REQUESTS.inc();
+
+    if !is_admin_request(&req) {
+        return HttpResponse::Unauthorized().body("unauthorized");
+    }
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load academic calendar: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
+        }
+    };
+
+    if let Some(err) = new_override.validation_error(&calendar) {
+        return HttpResponse::BadRequest().body(err);
+    }
+
+    let created = match add_schedule_override(&database, &group_name.group_name, &new_override).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return HttpResponse::InternalServerError().body("internal_server_error"),
+        Err(err) => {
+            error!("failed to save schedule override: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
+        }
+    };
+
+    // web servers drop the cached schedule of the group
+    if let Err(err) = notify_schedule_updated(&**database, &group_name.group_name).await {
+        error!("failed to notify about schedule override: {}", err);
+    }
+
+    HttpResponse::Created().json(created)
+}
+
+#[delete("/api/v1/groups/{group_name}/overrides/{override_id}")]
+async fn delete_schedule_override(req: HttpRequest, database_pool: web::Data<DatabasePool>, schedule_override: web::Path<ScheduleOverrideId>) -> impl Responder {
+    info!("delete schedule override request");
+
+    SCHEDULE_OVERRIDE_REQUESTS.inc();
+
+    if !is_admin_request(&req) {
+        return HttpResponse::Unauthorized().body("unauthorized");
+    }
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    match remove_schedule_override(&database, &schedule_override.group_name, schedule_override.override_id).await {
+        Ok(true) => {
+            if let Err(err) = notify_schedule_updated(&**database, &schedule_override.group_name).await {
+                error!("failed to notify about schedule override: {}", err);
+            }
+            HttpResponse::NoContent().finish()
+        },
+        Ok(false) => HttpResponse::NotFound().body("schedule_override_not_found"),
+        Err(err) => {
+            error!("failed to remove schedule override: {}", err);
+            HttpResponse::InternalServerError().body("internal_server_error")
+        }
+    }
+}
+
+// "Authorization: Bearer <ADMIN_TOKEN>"
+fn is_admin_request(req: &HttpRequest) -> bool {
+    let token = match admin_token() {
+        Some(v) => v,
+        None => return false,
+    };
+
+    req.headers().get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| constant_time_eq(v.as_bytes(), token.as_bytes()))
+        .unwrap_or(false)
+}
+
+#[get("/v1/schedule/{group_name}")]
+async fn student_schedule(database_pool: web::Data<DatabasePool>, schedule_fetches: web::Data<GroupScheduleFetches>, group_name: web::Path<GroupName>, student: web::Query<StudentLastName>) -> impl Responder {
+    info!("personal schedule request");
+
+    PERSONAL_SCHEDULE_REQUESTS.inc();
+
+    let client = reqwest::Client::new();
+    let mut database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let students = match student_subjects_for_group(&database, &group_name.group_name).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load student subjects: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    let student = match find_student(&students, &student.last_name) {
+        Ok(v) => v,
+        Err(StudentLookupError::NotFound) => return HttpResponse::NotFound().body("student_not_found"),
+        Err(StudentLookupError::Ambiguous) => return HttpResponse::Conflict().body("student_name_ambiguous"),
+    };
+
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load academic calendar: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+    let term = calendar.term_at(kyiv_date(Utc::now()));
+
+    let schedule = match load_or_fetch_group_schedule(&database_pool, &mut database, &client, &schedule_fetches, &term.term, &group_name.group_name).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to get group schedule: {}", err);
+            return HttpResponse::InternalServerError().body("failed to get group schedule");
+        }
+    };
+
+    let lesson_times = match lesson_times_for_academic_year(&database, term.academic_year).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load lesson times: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    let schedule = schedule_for_student(schedule, &students, student);
+    let schedule = match enrich_group_schedule(&database, schedule, &lesson_times).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to enrich group schedule: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    HttpResponse::Ok().json(schedule)
+}
+
+#[get("/groups/{group_name}/calendar.ics")]
+async fn group_calendar(database_pool: web::Data<DatabasePool>, schedule_fetches: web::Data<GroupScheduleFetches>, group_name: web::Path<GroupName>) -> impl Responder {
+    info!("group calendar request");
+
+    GROUP_CALENDAR_REQUESTS.inc();
+
+    let client = reqwest::Client::new();
+    let mut database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load academic calendar: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+    let term = calendar.term_at(kyiv_date(Utc::now()));
+
+    let schedule = match load_or_fetch_group_schedule(&database_pool, &mut database, &client, &schedule_fetches, &term.term, &group_name.group_name).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to get group schedule: {}", err);
+            return HttpResponse::InternalServerError().body("failed to get group schedule");
+        }
+    };
+
+    let lesson_times = match lesson_times_for_academic_year(&database, term.academic_year).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load lesson times: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    let ical = group_schedule_to_ical(&group_name.group_name, &schedule, &calendar, &term, &lesson_times, Utc::now());
+
+    HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(ical)
 }
 
-#[derive(Deserialize)]
-struct SubjectName {
-    subject_name: String,
+#[get("/api/v1/groups/{group_name}/now")]
+async fn group_lesson_now(database_pool: web::Data<DatabasePool>, schedule_fetches: web::Data<GroupScheduleFetches>, group_name: web::Path<GroupName>, query: web::Query<LessonAtQuery>) -> impl Responder {
+    info!("group current lesson request");
+    group_lesson(&database_pool, &schedule_fetches, &group_name.group_name, query.at.unwrap_or_else(Utc::now), current_lesson).await
 }
 
-#[derive(Serialize)]
-struct SubjectResponse {
-    link: String,
-    emoji: String,
+#[get("/api/v1/groups/{group_name}/next")]
+async fn group_lesson_next(database_pool: web::Data<DatabasePool>, schedule_fetches: web::Data<GroupScheduleFetches>, group_name: web::Path<GroupName>, query: web::Query<LessonAtQuery>) -> impl Responder {
+    info!("group next lesson request");
+    group_lesson(&database_pool, &schedule_fetches, &group_name.group_name, query.at.unwrap_or_else(Utc::now), next_lesson).await
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    env_logger::init();
+async fn group_lesson(
+    database_pool: &DatabasePool,
+    schedule_fetches: &web::Data<GroupScheduleFetches>,
+    group_name: &str,
+    at: DateTime<Utc>,
+    find_lesson: fn(&GroupSchedule, &AcademicCalendar, &LessonTimes, DateTime<Utc>) -> Option<ScheduledLesson>
+) -> HttpResponse {
+    GROUP_LESSON_REQUESTS.inc();
 
-    let args: Vec<String> = env::vars().map(|v| v.0).collect();
-    let contains_refresh_groups = args.contains(&"KPIEXPORT_REFRESH_GROUPS_JOB".to_string());
-    let contains_refresh_schedule = args.contains(&"KPIEXPORT_REFRESH_SCHEDULE_JOB".to_string());
+    let client = reqwest::Client::new();
+    let mut database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
 
-    if contains_refresh_groups {
-        println!("starting refresh groups job");
-        refresh_groups().await
-    } else if contains_refresh_schedule {
-        println!("starting refresh schedule job");
-        refresh_schedule().await
-    } else {
-        info!("starting kpiexport webserver");
-        start_webserver().await
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load academic calendar: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+    let term = calendar.term_at(kyiv_date(at));
+
+    let schedule = match load_or_fetch_group_schedule(database_pool, &mut database, &client, schedule_fetches, &term.term, group_name).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to get group schedule: {}", err);
+            return HttpResponse::InternalServerError().body("failed to get group schedule");
+        }
+    };
+
+    let lesson_times = match lesson_times_for_academic_year(&database, term.academic_year).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load lesson times: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    match find_lesson(&schedule, &calendar, &lesson_times, at) {
+        Some(lesson) => HttpResponse::Ok().json(lesson),
+        None => HttpResponse::NotFound().body("lesson_not_found"),
     }
 }
 
-async fn start_webserver() -> std::io::Result<()> {
-    HttpServer::new(|| App::new()
-        .service(healthz)
-        .service(metrics)
-        .service(service_version)
-        .service(groups)
-        .service(group_schedule)
-        .service(subject_id_by_name)
-        .service(subject_info_by_id)
-    )
-        .bind(bind_address())?
-        .run()
-        .await
+#[get("/api/v1/lecturers")]
+async fn lecturer_search(database_pool: web::Data<DatabasePool>, query: web::Query<LecturerSearchQuery>) -> impl Responder {
+    info!("lecturer search request");
+
+    LECTURER_SEARCH_REQUESTS.inc();
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let lecturers = match all_lecturers(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to read lecturers: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
+        }
+    };
+
+    let matches: Vec<Lecturer> = lecturers.into_iter()
+        .filter(|lecturer| person_name_contains(&lecturer.name, &query.q))
+        .take(LECTURER_SEARCH_RESULTS)
+        .collect();
+
+    HttpResponse::Ok().json(matches)
 }
 
-#[get("/groups/{group_name}")]
-async fn group_schedule(group_name: web::Path<GroupName>) -> impl Responder {
-    info!("group schedule request");
+#[get("/api/v1/lecturers/{lecturer_id}/schedule")]
+async fn lecturer_schedule_by_id(database_pool: web::Data<DatabasePool>, lecturer_id: web::Path<(i32,)>) -> impl Responder {
+    info!("lecturer schedule request");
 
-    GROUP_SCHEDULE_REQUESTS.inc();
+    LECTURER_SCHEDULE_REQUESTS.inc();
 
-    let client = reqwest::Client::new();
-    let database = match database_connection().await {
+    let database = match database_pool.get().await {
         Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let lecturer = match lecturer_by_id(&database, lecturer_id.0).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return HttpResponse::NotFound().body("lecturer_not_found"),
         Err(err) => {
-            error!("failed to connect to database: {}", err);
+            error!("failed to read lecturer: {}", err);
             return HttpResponse::InternalServerError().body("internal server error");
         }
     };
 
-    let schedule_from_database = load_group_schedule_from_database(&database, &group_name.group_name).await
-        .ok()
-        .and_then(|v| v);
+    let group_entries = match lecturer_schedule_entries(&database, &lecturer.name).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to read lecturer schedule: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
 
-    let schedule = match schedule_from_database {
-        Some(v) => {
-            info!("from cache: {}", group_name.group_name);
-            v
-        },
-        None => {
-            info!("loading: {}", group_name.group_name);
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load academic calendar: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+    let term = calendar.term_at(kyiv_date(Utc::now()));
 
-            if let Err(err) = remove_old_schedule_from_database(&database, &group_name.group_name).await {
-                error!("failed to remove schedule from database: {}", err);
-            }
-            info!("removed old schedule from database if present");
-            
-            let schedule = match group_schedule_by_name(&client, &Term::current(), &group_name.group_name).await {
-                Ok(v) => v,
-                Err(err) => {
-                    error!("failed to get group schedule: {}", err);
-                    return HttpResponse::InternalServerError().body("failed to get group schedule");
-                }
-            };
-
-            if let Err(err) = save_schedule_to_database(&database, &group_name.group_name, &schedule).await {
-                error!("failed to save schedule to database: {}", err);
+    let lesson_times = match lesson_times_for_academic_year(&database, term.academic_year).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load lesson times: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    let mut entries = Vec::new();
+    for v in merge_lecturer_schedule(&lecturer.name, group_entries) {
+        match enrich_schedule_entry(&database, v.entry, &lesson_times).await {
+            Ok(entry) => entries.push(MergedScheduleEntry { entry, ..v }),
+            Err(err) => {
+                error!("failed to enrich lecturer schedule: {}", err);
+                return HttpResponse::InternalServerError().body("internal server error");
             }
+        }
+    }
+
+    HttpResponse::Ok().json(LecturerScheduleResponse {
+        lecturer,
+        entries,
+    })
+}
+
+#[get("/api/v1/rooms/free")]
+async fn free_rooms_at(database_pool: web::Data<DatabasePool>, query: web::Query<FreeRoomsQuery>) -> impl Responder {
+    info!("free rooms request");
+
+    FREE_ROOMS_REQUESTS.inc();
+
+    if !(0..=1).contains(&query.week) || !(0..=6).contains(&query.day) {
+        return HttpResponse::BadRequest().body("invalid_time_slot");
+    }
+
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
 
-            schedule
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load academic calendar: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
         }
     };
+    let term = calendar.term_at(kyiv_date(Utc::now()));
 
-    let mut entries = Vec::new();
+    // number of lessons a day depends on the bell schedule of the academic year
+    let lesson_times = match lesson_times_for_academic_year(&database, term.academic_year).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load lesson times: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
+        }
+    };
+    if u8::try_from(query.index).ok().and_then(|v| lesson_times.for_index(v)).is_none() {
+        return HttpResponse::BadRequest().body("invalid_time_slot");
+    }
 
-    for mut entry in schedule.entries {
-        let subject_id = database::subject_id_by_name(&database, &entry.names()[0]).await.unwrap();
-        if let Some(subject_id) = subject_id {
-            entry = entry.with_subject_id(subject_id);
+    let known_locations = match all_locations(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to read locations: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
         }
+    };
 
-        entry = entry.clone().with_locations(entry.locations().iter().map(|v| format!("НТУУ \"КПІ\" ({})", v)).collect());
+    let occupied_locations = match locations_at(&database, query.week, query.day, query.index).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to read occupied locations: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
+        }
+    };
 
-        entries.push(entry);
-    }
+    HttpResponse::Ok().json(free_rooms(&known_locations, &occupied_locations, query.building.as_deref()))
+}
 
-    let schedule = GroupSchedule {
-        entries,
-        source: schedule.source,
+#[get("/api/v1/rooms/{room_name}/schedule")]
+async fn room_schedule_by_name(database_pool: web::Data<DatabasePool>, room_name: web::Path<RoomName>) -> impl Responder {
+    info!("room schedule request");
+
+    ROOM_SCHEDULE_REQUESTS.inc();
+
+    let room = match parse_location(&room_name.room_name) {
+        Some(v) => v.room,
+        None => return HttpResponse::NotFound().body("room_not_found"),
     };
 
-    HttpResponse::Ok().json(schedule)
+    let database = match database_pool.get().await {
+        Ok(v) => v,
+        Err(err) => return database_pool_error(err),
+    };
+
+    let group_entries = match room_schedule_entries(&database, &room.name).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to read room schedule: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    let calendar = match load_academic_calendar(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load academic calendar: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+    let term = calendar.term_at(kyiv_date(Utc::now()));
+
+    let lesson_times = match lesson_times_for_academic_year(&database, term.academic_year).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to load lesson times: {}", err);
+            return HttpResponse::InternalServerError().body("internal server error");
+        }
+    };
+
+    let mut entries = Vec::new();
+    for v in room_schedule(&room, group_entries) {
+        match enrich_schedule_entry(&database, v.entry, &lesson_times).await {
+            Ok(entry) => entries.push(MergedScheduleEntry { entry, ..v }),
+            Err(err) => {
+                error!("failed to enrich room schedule: {}", err);
+                return HttpResponse::InternalServerError().body("internal server error");
+            }
+        }
+    }
+
+    HttpResponse::Ok().json(RoomScheduleResponse {
+        room,
+        entries,
+    })
 }
 
 #[get("/groups")]
-async fn groups() -> impl Responder {
+async fn groups(req: HttpRequest, database_pool: web::Data<DatabasePool>, schedule_fetches: web::Data<GroupScheduleFetches>) -> impl Responder {
     info!("groups list request");
 
     GROUPS_LIST_REQUESTS.inc();
 
     let client = reqwest::Client::new();
 
-    let database = match database_connection().await {
+    let database = match database_pool.get().await {
         Ok(v) => v,
-        Err(err) => {
-            error!("failed to connect to database: {}", err);
-            return HttpResponse::InternalServerError().body("internal_server_error");
-        }
+        Err(err) => return database_pool_error(err),
     };
 
     let total_groups: i64 = match total_groups_saved(&database).await {
@@ -189,8 +1126,8 @@ async fn groups() -> impl Responder {
     };
 
     let groups = if total_groups == 0 {
-        let groups = rozklad_parser::get_groups(&client).await;
-        info!("got {} groups from parser", groups.len());
+        let groups = schedule_fetches.sources().groups(&client).await;
+        info!("got {} groups from schedule sources", groups.len());
         for group in &groups {
             if let Err(err) = add_group(&database, group).await {
                 error!("failed to save entry to database: {}", err);
@@ -200,24 +1137,24 @@ async fn groups() -> impl Responder {
 
         groups
     } else {
-        let mut groups = Vec::new();
-
-        let res = match database.query("select group_name from schedule_groups", &[]).await {
+        match all_groups(&database).await {
             Ok(v) => v,
             Err(err) => {
                 error!("failed to read groups from schedule_groups: {}", err);
                 return HttpResponse::InternalServerError().body("internal_server_error");
             }
-        };
-
-        for row in res {
-            groups.push(row.get("group_name"));
         }
+    };
 
-        groups
+    let updated_at = match groups_updated_at(&database).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to read groups update time from schedule_groups: {}", err);
+            return HttpResponse::InternalServerError().body("internal_server_error");
+        }
     };
 
-    HttpResponse::Ok().json(groups)
+    cacheable_json(&req, &groups, updated_at, GROUPS_MAX_AGE_SECONDS)
 }
 
 #[get("/healthz")]
@@ -226,7 +1163,12 @@ async fn healthz() -> impl Responder {
 }
 
 #[get("/metrics")]
-async fn metrics() -> impl Responder {
+async fn metrics(database_pool: web::Data<DatabasePool>) -> impl Responder {
+    let pool_status = database_pool.status();
+    DATABASE_POOL_MAX_SIZE.set(pool_status.max_size as i64);
+    DATABASE_POOL_SIZE.set(pool_status.size as i64);
+    DATABASE_POOL_AVAILABLE.set(pool_status.available as i64);
+
     let mut buffer = vec![];
     let encoder = TextEncoder::new();
     let metric_families = prometheus::gather();
@@ -253,13 +1195,10 @@ async fn service_version() -> impl Responder {
 }
 
 #[get("/api/v1/subjects")]
-async fn subject_id_by_name(subject_name: web::Query<SubjectName>) -> impl Responder {
-    let database = match database_connection().await {
+async fn subject_id_by_name(database_pool: web::Data<DatabasePool>, subject_name: web::Query<SubjectName>) -> impl Responder {
+    let database = match database_pool.get().await {
         Ok(v) => v,
-        Err(err) => {
-            error!("failed to connect to database: {}", err);
-            return HttpResponse::InternalServerError().body("internal_server_error");
-        }
+        Err(err) => return database_pool_error(err),
     };
 
     let res = match database.query(
@@ -281,13 +1220,10 @@ async fn subject_id_by_name(subject_name: web::Query<SubjectName>) -> impl Respo
 }
 
 #[get("/api/v1/subjects/{subject_id}")]
-async fn subject_info_by_id(subject_id: web::Path<(u32,)>) -> impl Responder {
-    let database = match database_connection().await {
+async fn subject_info_by_id(database_pool: web::Data<DatabasePool>, subject_id: web::Path<(u32,)>) -> impl Responder {
+    let database = match database_pool.get().await {
         Ok(v) => v,
-        Err(err) => {
-            error!("failed to connect to database: {}", err);
-            return HttpResponse::InternalServerError().body("internal_server_error");
-        }
+        Err(err) => return database_pool_error(err),
     };
 
     let res = match database.query(
@@ -309,41 +1245,187 @@ async fn subject_info_by_id(subject_id: web::Path<(u32,)>) -> impl Responder {
     })
 }
 
-async fn load_group_schedule_from_database(database: &tokio_postgres::Client, group_name: &str) -> Result<Option<GroupSchedule>, PersistenceError> {
-    let res = match database.query(
-        "select * from schedule where group_name = $1 and updated_at > now() - $2::text::interval",
-        &[&group_name, &format!("{}days", 14)]
-    ).await {
-        Ok(v) => v,
-        Err(err) => {
-            error!("failed to execute database query: {}", err);
-            return Err(PersistenceError::FailedToLoad);
+// pool is saturated when all connections are busy for longer than the wait timeout
+fn database_pool_error(err: PoolError) -> HttpResponse {
+    match err {
+        PoolError::Timeout(timeout_type) => {
+            warn!("timed out getting database connection from pool: {:?}", timeout_type);
+            DATABASE_POOL_TIMEOUTS.inc();
+            HttpResponse::ServiceUnavailable().body("database_unavailable")
+        },
+        other => {
+            error!("failed to get database connection from pool: {}", other);
+            HttpResponse::InternalServerError().body("internal server error")
         }
-    };
+    }
+}
+
+// adds subject ids, lesson times and full location names
+async fn enrich_group_schedule(database: &tokio_postgres::Client, schedule: GroupSchedule, lesson_times: &LessonTimes) -> Result<GroupSchedule, DatabaseError> {
+    let mut entries = Vec::new();
+    let mut cancelled_entries = Vec::new();
+    let mut exceptions = Vec::new();
+
+    for entry in schedule.entries {
+        entries.push(enrich_schedule_entry(database, entry, lesson_times).await?);
+    }
+
+    for entry in schedule.cancelled_entries {
+        cancelled_entries.push(enrich_schedule_entry(database, entry, lesson_times).await?);
+    }
+
+    for exception in schedule.exceptions {
+        let mut exception_entries = Vec::new();
+        let mut exception_cancelled_entries = Vec::new();
+
+        for entry in exception.entries {
+            exception_entries.push(enrich_schedule_entry(database, entry, lesson_times).await?);
+        }
+        for entry in exception.cancelled_entries {
+            exception_cancelled_entries.push(enrich_schedule_entry(database, entry, lesson_times).await?);
+        }
+
+        exceptions.push(ScheduleException {
+            entries: exception_entries,
+            cancelled_entries: exception_cancelled_entries,
+            ..exception
+        });
+    }
+
+    Ok(GroupSchedule {
+        entries,
+        source: schedule.source,
+        updated_at: schedule.updated_at,
+        stale: schedule.stale,
+        cancelled_entries,
+        exceptions,
+    })
+}
+
+async fn enrich_schedule_entry(database: &tokio_postgres::Client, entry: GroupScheduleEntry, lesson_times: &LessonTimes) -> Result<GroupScheduleEntry, DatabaseError> {
+    let mut entry = entry;
+
+    let subject_id = database::subject_id_by_name(database, &entry.names()[0]).await?;
+    if let Some(subject_id) = subject_id {
+        entry = entry.with_subject_id(subject_id);
+    }
 
-    let mut source: Option<GroupScheduleSource> = None;
-    let mut entries: Vec<GroupScheduleEntry> = vec![];
+    entry = entry.clone().with_locations(entry.locations().iter().map(|v| format!("НТУУ \"КПІ\" ({})", v)).collect());
 
-    for row in res {
-        entries.push(GroupScheduleEntry::new(
-                ScheduleWeek::from_index(row.get::<&str, i16>("week") as u8),
-                ScheduleDay::from_index(row.get::<&str, i16>("day") as u8),
-                row.get::<&str, i16>("index") as u8
-            )
-            .with_names(row.get("names"))
-            .with_lecturers( row.get("lecturers"))
-            .with_locations(row.get("locations"))
-        );
+    if let Some(lesson_time) = lesson_times.for_index(entry.index) {
+        entry = entry.with_lesson_time(lesson_time);
+    }
+
+    Ok(entry)
+}
+
+// only one request per group fetches schedule from rozklad at a time, others wait for its result.
+// Stale schedule is returned right away and fetched again in background, so rozklad being down is not an error
+// for groups which were fetched before.
+async fn load_or_fetch_group_schedule(
+    database_pool: &DatabasePool,
+    database: &mut tokio_postgres::Client,
+    client: &reqwest::Client,
+    schedule_fetches: &web::Data<GroupScheduleFetches>,
+    term: &Term,
+    group_name: &str
+) -> Result<GroupSchedule, RozkladParseError> {
+    match load_cached_group_schedule(database, group_name).await {
+        Some(schedule) if schedule.stale => {
+            STALE_SCHEDULE_RESPONSES.inc();
+            refresh_stale_group_schedule(database_pool.clone(), schedule_fetches.clone(), *term, group_name.to_string());
+            return Ok(schedule);
+        },
+        Some(schedule) => return Ok(schedule),
+        None => (),
+    }
+
+    let (schedule, coalesced) = schedule_fetches.run(group_name, || async move {
+        // fetch by another request could have finished right after the check above
+        if let Some(schedule) = load_cached_group_schedule(database, group_name).await {
+            return Ok(schedule);
+        }
+
+        fetch_group_schedule(database, client, schedule_fetches.sources(), term, group_name).await
+            .map_err(|err| err.to_string())
+    }).await;
+
+    if coalesced {
+        info!("used schedule fetched by another request: {}", group_name);
+        COALESCED_SCHEDULE_REQUESTS.inc();
+    }
+
+    schedule.map_err(|description| RozkladParseError::ScheduleFetchFailed { description })
+}
+
+async fn load_cached_group_schedule(database: &tokio_postgres::Client, group_name: &str) -> Option<GroupSchedule> {
+    let schedule = load_group_schedule_from_database(database, group_name, schedule_max_age()).await
+        .ok()
+        .and_then(|v| v);
 
-        if source.is_none() {
-            let source_str: String = row.get("source");
+    match &schedule {
+        Some(schedule) if schedule.stale => info!("from cache (stale): {}", group_name),
+        Some(_) => info!("from cache: {}", group_name),
+        None => (),
+    }
+
+    schedule
+}
+
+// runs after the response with the stale schedule is sent, at most once per group in
+// stale_schedule_refresh_interval. Saved schedule is picked up by the next request.
+fn refresh_stale_group_schedule(database_pool: DatabasePool, schedule_fetches: web::Data<GroupScheduleFetches>, term: Term, group_name: String) {
+    if !schedule_fetches.start_stale_refresh(&group_name) {
+        return;
+    }
 
-            source = GroupScheduleSource::from_string(&source_str);
-            if source.is_none() {
-                error!("unknown from group schedule source in database: {}", &source_str);
+    actix_rt::spawn(async move {
+        let mut database = match database_pool.get().await {
+            Ok(v) => v,
+            Err(err) => {
+                error!("failed to get database connection to refresh stale schedule: {}", err);
+                return;
             }
+        };
+        let database: &mut tokio_postgres::Client = &mut database;
+        let client = reqwest::Client::new();
+        let group_name = group_name.as_str();
+
+        let sources = schedule_fetches.sources();
+        let (schedule, _) = schedule_fetches.run(group_name, || async move {
+            fetch_group_schedule(database, &client, sources, &term, group_name).await
+                .map_err(|err| err.to_string())
+        }).await;
+
+        match schedule {
+            Ok(_) => info!("refreshed stale schedule: {}", group_name),
+            Err(err) => warn!("failed to refresh stale schedule of {}: {}", group_name, err),
         }
+    });
+}
+
+async fn fetch_group_schedule(
+    database: &mut tokio_postgres::Client,
+    client: &reqwest::Client,
+    sources: &ScheduleSources,
+    term: &Term,
+    group_name: &str
+) -> Result<GroupSchedule, RozkladParseError> {
+    info!("loading: {}", group_name);
+
+    // stored schedule is replaced only after it was fetched
+    let mut schedule = sources.group_schedule(client, term, group_name).await?;
+    schedule.updated_at = Some(Utc::now());
+
+    // changes are delivered to webhooks by deliver_webhooks_periodically
+    match save_group_schedule(database, group_name, &schedule).await {
+        Ok(changes) => info!("saved schedule for {}, {} changes", group_name, changes.len()),
+        Err(err) => error!("failed to save schedule to database: {}", err),
+    }
+
+    if let Err(err) = add_lecturers(database, &schedule_lecturers(&schedule)).await {
+        error!("failed to save lecturers to database: {}", err);
     }
 
-    Ok(source.map(|source| GroupSchedule { source: Some(source), entries }))
+    Ok(schedule)
 }
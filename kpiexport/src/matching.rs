@@ -0,0 +1,138 @@
+use levenshtein::levenshtein;
+use translit::{Gost779B, Language, ToLatin};
+
+lazy_static! {
+    static ref TRANSLITERATOR: Gost779B = Gost779B::new(Language::Ua);
+}
+
+// there are several popular ways to write ukrainian names in latin (passport, GOST, "as you hear it"), so
+// both sides are reduced to the same simplified form before comparing.
+const LATIN_SPELLING_VARIANTS: &[(&str, &str)] = &[
+    ("shch", "1"),
+    ("shh", "1"),
+    ("zh", "2"),
+    ("ch", "3"),
+    ("sh", "4"),
+    ("kh", "x"),
+    ("ts", "c"),
+    ("h", "g"),
+    ("w", "v"),
+    ("yu", "u"),
+    ("iu", "u"),
+    ("ju", "u"),
+    ("ya", "a"),
+    ("ia", "a"),
+    ("ja", "a"),
+    ("ye", "e"),
+    ("ie", "e"),
+    ("je", "e"),
+    ("yi", "i"),
+    ("ji", "i"),
+    ("y", "i"),
+    ("j", "i"),
+];
+
+// lowercase latin letters and digits only
+pub fn to_latin(text: &str) -> String {
+    TRANSLITERATOR.to_latin(&text.to_lowercase())
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+pub fn normalize_person_name(name: &str) -> String {
+    LATIN_SPELLING_VARIANTS.iter()
+        .fold(to_latin(name), |name, (variant, replacement)| name.replace(variant, replacement))
+}
+
+pub fn is_same_person_name(a: &str, b: &str) -> bool {
+    normalize_person_name(a) == normalize_person_name(b)
+}
+
+// "ivanenko" finds "доц. Іваненко І. І."
+pub fn person_name_contains(name: &str, query: &str) -> bool {
+    normalize_person_name(name).contains(&normalize_person_name(query))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupMatch {
+    pub group_name: String,
+    pub distance: usize,
+}
+
+// "ІП-82", "іп82" and "ip-82" are all the same group
+pub fn normalize_group_name(name: &str) -> String {
+    to_latin(name)
+}
+
+// closest groups first
+pub fn rank_group_names(query: &str, groups: &[String]) -> Vec<GroupMatch> {
+    let query = normalize_group_name(query);
+
+    let mut matches: Vec<GroupMatch> = groups.iter()
+        .map(|group_name| GroupMatch {
+            group_name: group_name.clone(),
+            distance: levenshtein(&query, &normalize_group_name(group_name)),
+        })
+        .collect();
+
+    matches.sort_by(|a, b| a.distance.cmp(&b.distance).then_with(|| a.group_name.cmp(&b.group_name)));
+    matches
+}
+
+// canonical group name if exactly one group is the same as query after normalization
+pub fn resolve_group_name(query: &str, groups: &[String]) -> Option<String> {
+    let query = normalize_group_name(query);
+
+    let mut candidates = groups.iter()
+        .filter(|group_name| normalize_group_name(group_name) == query);
+
+    match (candidates.next(), candidates.next()) {
+        (Some(group_name), None) => Some(group_name.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn person_names_match_across_transliterations() {
+        assert!(is_same_person_name("Волобуєв", "Volobuev"));
+        assert!(is_same_person_name("Волобуєв", "volobuiev"));
+        assert!(is_same_person_name("Харченко", "Kharchenko"));
+        assert!(is_same_person_name("Шевченко", "Shevchenko"));
+        assert!(is_same_person_name("Сергій", "Serhiy"));
+        assert!(is_same_person_name("Юрченко", "Iurchenko"));
+        assert!(!is_same_person_name("Шевченко", "Savchenko"));
+    }
+
+    #[test]
+    fn person_name_is_found_by_part() {
+        assert!(person_name_contains("доц. Іваненко Іван Іванович", "ivanenko"));
+        assert!(person_name_contains("доц. Іваненко Іван Іванович", "Іваненко І"));
+        assert!(!person_name_contains("доц. Іваненко Іван Іванович", "Петренко"));
+    }
+
+    fn groups() -> Vec<String> {
+        vec!["ІП-81".into(), "ІП-82".into(), "ІП-82мп".into(), "ІС-82".into()]
+    }
+
+    #[test]
+    fn group_name_is_resolved_from_latin_and_lowercase() {
+        assert_eq!(resolve_group_name("ip-82", &groups()), Some("ІП-82".to_string()));
+        assert_eq!(resolve_group_name("іп82", &groups()), Some("ІП-82".to_string()));
+        assert_eq!(resolve_group_name("ІП-83", &groups()), None);
+    }
+
+    #[test]
+    fn group_names_are_ranked_by_distance() {
+        let matches = rank_group_names("ip-82", &groups());
+
+        assert_eq!(matches[0].group_name, "ІП-82");
+        assert_eq!(matches[0].distance, 0);
+        assert_eq!(matches[1].group_name, "ІП-81");
+        assert_eq!(matches[1].distance, 1);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::models::schedule::GroupScheduleEntry;
+
+// lesson shared by several groups, used for lecturer and room schedules
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedScheduleEntry {
+    #[serde(flatten)]
+    pub entry: GroupScheduleEntry,
+    pub groups: Vec<String>, // groups attending the lesson
+}
+
+// shared lessons are stored once for every group attending them, those are merged into a single entry
+pub fn merge_group_entries(group_entries: Vec<(String, GroupScheduleEntry)>) -> Vec<MergedScheduleEntry> {
+    let mut merged: Vec<MergedScheduleEntry> = Vec::new();
+
+    for (group_name, entry) in group_entries {
+        let existing = merged.iter_mut().find(|v| v.entry.week == entry.week
+            && v.entry.day == entry.day
+            && v.entry.index == entry.index
+            && v.entry.names == entry.names
+        );
+
+        match existing {
+            Some(existing) => {
+                push_missing(&mut existing.groups, &[group_name]);
+                push_missing(&mut existing.entry.lecturers, &entry.lecturers);
+                push_missing(&mut existing.entry.locations, &entry.locations);
+                existing.entry.lesson_type = existing.entry.lesson_type.or(entry.lesson_type);
+            },
+            None => merged.push(MergedScheduleEntry {
+                entry,
+                groups: vec![group_name],
+            }),
+        }
+    }
+
+    for v in &mut merged {
+        v.groups.sort();
+    }
+    merged.sort_by_key(|v| (v.entry.week.to_index(), v.entry.day.to_index(), v.entry.index));
+    merged
+}
+
+fn push_missing(values: &mut Vec<String>, new_values: &[String]) {
+    for value in new_values {
+        if !values.contains(value) {
+            values.push(value.clone());
+        }
+    }
+}
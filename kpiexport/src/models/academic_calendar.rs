@@ -0,0 +1,298 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use tokio_postgres::Client;
+use crate::models::schedule::ScheduleWeek;
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Term {
+    First,
+    Second
+}
+
+#[derive(Debug, Clone)]
+pub struct AcademicTerm {
+    pub academic_year: i32, // year the academic year starts in, so both terms of 2021/2022 belong to 2021
+    pub term: Term,
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate,
+    pub first_week_starts_on: NaiveDate, // calendar week starting on this day is ScheduleWeek::First
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExclusionKind {
+    Holiday,
+    ExamSession,
+}
+
+// days without regular lessons
+#[derive(Debug, Clone)]
+pub struct CalendarExclusion {
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate, // inclusive
+    pub kind: ExclusionKind,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AcademicCalendar {
+    terms: Vec<AcademicTerm>,
+    exclusions: Vec<CalendarExclusion>,
+}
+
+impl Term {
+
+    pub fn from_index(index: i16) -> Option<Self> {
+        match index {
+            1 => Some(Self::First),
+            2 => Some(Self::Second),
+            _ => None,
+        }
+    }
+}
+
+impl AcademicTerm {
+
+    // used when academic calendar has no data for the date: first term is from September till the end of
+    // December (January is still considered to be first term), second term is from February till June 10th.
+    pub fn estimated(date: NaiveDate) -> Self {
+        let month0 = date.month0();
+        let (academic_year, term) = if month0 == 0 {
+            (date.year() - 1, Term::First)
+        } else if month0 >= 6 {
+            (date.year(), Term::First)
+        } else {
+            (date.year() - 1, Term::Second)
+        };
+
+        let (starts_on, ends_on) = match term {
+            Term::First => (NaiveDate::from_ymd(academic_year, 9, 1), NaiveDate::from_ymd(academic_year, 12, 31)),
+            Term::Second => (NaiveDate::from_ymd(academic_year + 1, 2, 1), NaiveDate::from_ymd(academic_year + 1, 6, 10)),
+        };
+
+        Self {
+            academic_year,
+            term,
+            starts_on,
+            ends_on,
+            first_week_starts_on: week_start(starts_on),
+        }
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.starts_on && date <= self.ends_on
+    }
+
+    pub fn week(&self, date: NaiveDate) -> ScheduleWeek {
+        let weeks_since_first = (date - week_start(self.first_week_starts_on)).num_days().div_euclid(7);
+        if weeks_since_first % 2 == 0 {
+            ScheduleWeek::First
+        } else {
+            ScheduleWeek::Second
+        }
+    }
+}
+
+impl CalendarExclusion {
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.starts_on && date <= self.ends_on
+    }
+}
+
+impl ExclusionKind {
+
+    pub fn from_string(name: &str) -> Option<Self> {
+        match name {
+            "holiday" => Some(Self::Holiday),
+            "exam_session" => Some(Self::ExamSession),
+            _ => None
+        }
+    }
+}
+
+impl AcademicCalendar {
+
+    pub fn new(terms: Vec<AcademicTerm>, exclusions: Vec<CalendarExclusion>) -> Self {
+        Self {
+            terms,
+            exclusions,
+        }
+    }
+
+    // term containing the date. Between terms, the upcoming one is used so that schedule for it is
+    // available in advance. Terms of other academic years are not used, so the date is estimated once the
+    // calendar has no data for its academic year (or the next one).
+    pub fn term_at(&self, date: NaiveDate) -> AcademicTerm {
+        if let Some(term) = self.terms.iter().find(|v| v.contains(date)) {
+            return term.clone();
+        }
+
+        let academic_year = AcademicTerm::estimated(date).academic_year;
+
+        let upcoming = self.terms.iter()
+            .filter(|v| v.starts_on > date && (v.academic_year == academic_year || v.academic_year == academic_year + 1))
+            .min_by_key(|v| v.starts_on);
+        let latest_ended = self.terms.iter()
+            .filter(|v| v.ends_on < date && v.academic_year == academic_year)
+            .max_by_key(|v| v.ends_on);
+
+        upcoming.or(latest_ended)
+            .cloned()
+            .unwrap_or_else(|| AcademicTerm::estimated(date))
+    }
+
+    pub fn exclusion_at(&self, date: NaiveDate) -> Option<&CalendarExclusion> {
+        self.exclusions.iter().find(|v| v.contains(date))
+    }
+
+    // schedule changes a lot around the start of the term, so refresh jobs run more often then.
+    pub fn is_near_term_start(&self, date: NaiveDate) -> bool {
+        let term = self.term_at(date);
+        date >= term.starts_on - Duration::days(21) && date <= term.starts_on + Duration::days(30)
+    }
+}
+
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+pub async fn load_academic_calendar(database: &Client) -> Result<AcademicCalendar, tokio_postgres::Error> {
+    let terms = database.query(
+        "select academic_year, term, starts_on, ends_on, first_week_starts_on from academic_terms",
+        &[]
+    ).await?;
+
+    let terms = terms.iter()
+        .filter_map(|row| {
+            let term_index: i16 = row.get("term");
+            let term = Term::from_index(term_index);
+            if term.is_none() {
+                error!("unknown term in academic calendar: {}", term_index);
+            }
+
+            term.map(|term| AcademicTerm {
+                academic_year: row.get("academic_year"),
+                term,
+                starts_on: row.get("starts_on"),
+                ends_on: row.get("ends_on"),
+                first_week_starts_on: row.get("first_week_starts_on"),
+            })
+        })
+        .collect();
+
+    let exclusions = database.query(
+        "select starts_on, ends_on, kind, description from academic_calendar_exclusions",
+        &[]
+    ).await?;
+
+    let exclusions = exclusions.iter()
+        .filter_map(|row| {
+            let kind_str: String = row.get("kind");
+            let kind = ExclusionKind::from_string(&kind_str);
+            if kind.is_none() {
+                error!("unknown academic calendar exclusion kind: {}", kind_str);
+            }
+
+            kind.map(|kind| CalendarExclusion {
+                starts_on: row.get("starts_on"),
+                ends_on: row.get("ends_on"),
+                kind,
+                description: row.get("description"),
+            })
+        })
+        .collect();
+
+    Ok(AcademicCalendar::new(terms, exclusions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_calendar() -> AcademicCalendar {
+        AcademicCalendar::new(
+            vec![
+                AcademicTerm {
+                    academic_year: 2021,
+                    term: Term::First,
+                    starts_on: NaiveDate::from_ymd(2021, 9, 1),
+                    ends_on: NaiveDate::from_ymd(2021, 12, 24),
+                    first_week_starts_on: NaiveDate::from_ymd(2021, 8, 30),
+                },
+                AcademicTerm {
+                    academic_year: 2021,
+                    term: Term::Second,
+                    starts_on: NaiveDate::from_ymd(2022, 2, 7),
+                    ends_on: NaiveDate::from_ymd(2022, 5, 31),
+                    first_week_starts_on: NaiveDate::from_ymd(2022, 2, 7),
+                },
+            ],
+            vec![
+                CalendarExclusion {
+                    starts_on: NaiveDate::from_ymd(2021, 10, 14),
+                    ends_on: NaiveDate::from_ymd(2021, 10, 14),
+                    kind: ExclusionKind::Holiday,
+                    description: "День захисника України".into(),
+                }
+            ]
+        )
+    }
+
+    #[test]
+    fn term_at_prefers_upcoming_term_between_terms() {
+        let calendar = test_calendar();
+
+        assert_eq!(calendar.term_at(NaiveDate::from_ymd(2021, 11, 1)).term, Term::First);
+        assert_eq!(calendar.term_at(NaiveDate::from_ymd(2021, 12, 28)).starts_on, NaiveDate::from_ymd(2022, 2, 7));
+        assert_eq!(calendar.term_at(NaiveDate::from_ymd(2022, 1, 15)).starts_on, NaiveDate::from_ymd(2022, 2, 7));
+    }
+
+    #[test]
+    fn ended_term_is_kept_till_the_end_of_academic_year() {
+        let term = test_calendar().term_at(NaiveDate::from_ymd(2022, 6, 15));
+
+        assert_eq!(term.academic_year, 2021);
+        assert_eq!(term.starts_on, NaiveDate::from_ymd(2022, 2, 7));
+    }
+
+    #[test]
+    fn term_is_estimated_when_calendar_is_a_year_old() {
+        let calendar = test_calendar();
+
+        let term = calendar.term_at(NaiveDate::from_ymd(2022, 9, 15));
+        assert_eq!((term.academic_year, term.term), (2022, Term::First));
+        assert_eq!(term.starts_on, NaiveDate::from_ymd(2022, 9, 1));
+
+        let term = calendar.term_at(NaiveDate::from_ymd(2023, 3, 1));
+        assert_eq!((term.academic_year, term.term), (2022, Term::Second));
+    }
+
+    #[test]
+    fn term_is_estimated_when_calendar_is_empty() {
+        let calendar = AcademicCalendar::default();
+
+        let term = calendar.term_at(NaiveDate::from_ymd(2022, 1, 15));
+        assert_eq!(term.term, Term::First);
+        assert_eq!(term.academic_year, 2021);
+
+        let term = calendar.term_at(NaiveDate::from_ymd(2022, 3, 1));
+        assert_eq!(term.term, Term::Second);
+        assert_eq!(term.starts_on, NaiveDate::from_ymd(2022, 2, 1));
+    }
+
+    #[test]
+    fn week_parity() {
+        let term = test_calendar().term_at(NaiveDate::from_ymd(2021, 9, 1));
+
+        assert_eq!(term.week(NaiveDate::from_ymd(2021, 9, 5)), ScheduleWeek::First);
+        assert_eq!(term.week(NaiveDate::from_ymd(2021, 9, 6)), ScheduleWeek::Second);
+        assert_eq!(term.week(NaiveDate::from_ymd(2021, 9, 13)), ScheduleWeek::First);
+    }
+
+    #[test]
+    fn holidays_are_excluded() {
+        let calendar = test_calendar();
+
+        assert!(calendar.exclusion_at(NaiveDate::from_ymd(2021, 10, 13)).is_none());
+        assert_eq!(calendar.exclusion_at(NaiveDate::from_ymd(2021, 10, 14)).unwrap().kind, ExclusionKind::Holiday);
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use tokio_postgres::{Client, Transaction};
 
 pub async fn total_groups_saved(database: &Client) -> Result<i64, tokio_postgres::Error> {
@@ -5,6 +6,17 @@ pub async fn total_groups_saved(database: &Client) -> Result<i64, tokio_postgres
         .map(|v| v.get("count"))
 }
 
+pub async fn all_groups(database: &Client) -> Result<Vec<String>, tokio_postgres::Error> {
+    database.query("select group_name from schedule_groups", &[]).await
+        .map(|v| v.iter().map(|r| r.get("group_name")).collect())
+}
+
+// groups are replaced all at once by the refresh job, None when there are no groups yet
+pub async fn groups_updated_at(database: &Client) -> Result<Option<DateTime<Utc>>, tokio_postgres::Error> {
+    database.query_one("select max(inserted_at) as updated_at from schedule_groups", &[]).await
+        .map(|v| v.get("updated_at"))
+}
+
 pub async fn total_old_groups(database: &Client, days_diff: i64) -> Result<i64, tokio_postgres::Error> {
     database.query_one(
         "select count(*) from schedule_groups where inserted_at <= date_trunc('day', NOW() - cast($1::text as interval))",
@@ -0,0 +1,56 @@
+use serde::Serialize;
+use tokio_postgres::Client;
+use crate::models::schedule::{GroupSchedule, GroupScheduleEntry};
+use crate::models::schedule_queries::schedule_entry_from_row;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Lecturer {
+    pub id: i32,
+    pub name: String,
+}
+
+// lecturer names as they are written in the schedule, without duplicates and empty ones
+pub fn schedule_lecturers(schedule: &GroupSchedule) -> Vec<String> {
+    let mut lecturers: Vec<String> = schedule.entries.iter()
+        .flat_map(|entry| entry.lecturers.iter())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect();
+
+    lecturers.sort();
+    lecturers.dedup();
+    lecturers
+}
+
+pub async fn all_lecturers(database: &Client) -> Result<Vec<Lecturer>, tokio_postgres::Error> {
+    database.query("select id, name from lecturers order by name", &[]).await
+        .map(|v| v.iter().map(|r| Lecturer { id: r.get("id"), name: r.get("name") }).collect())
+}
+
+pub async fn lecturer_by_id(database: &Client, id: i32) -> Result<Option<Lecturer>, tokio_postgres::Error> {
+    database.query_opt("select id, name from lecturers where id = $1", &[&id]).await
+        .map(|v| v.map(|r| Lecturer { id: r.get("id"), name: r.get("name") }))
+}
+
+pub async fn add_lecturers(database: &Client, names: &[String]) -> Result<(), tokio_postgres::Error> {
+    database.execute(
+        "insert into lecturers (name) select distinct unnest($1::text[]) on conflict (name) do nothing",
+        &[&names]
+    ).await.map(|_| ())
+}
+
+// adds lecturers from every cached group schedule
+pub async fn index_lecturers(database: &Client) -> Result<u64, tokio_postgres::Error> {
+    database.execute(
+        "insert into lecturers (name) select distinct trim(name) from schedule, unnest(lecturers) as name where trim(name) <> '' on conflict (name) do nothing",
+        &[]
+    ).await
+}
+
+// entries of all groups the lecturer has lessons with, paired with the group name
+pub async fn lecturer_schedule_entries(database: &Client, lecturer_name: &str) -> Result<Vec<(String, GroupScheduleEntry)>, tokio_postgres::Error> {
+    database.query(
+        "select * from schedule where exists (select 1 from unnest(lecturers) as name where trim(name) = $1) order by week, day, index, group_name",
+        &[&lecturer_name]
+    ).await.map(|v| v.iter().map(|r| (r.get("group_name"), schedule_entry_from_row(r))).collect())
+}
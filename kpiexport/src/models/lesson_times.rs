@@ -0,0 +1,83 @@
+use chrono::NaiveTime;
+use tokio_postgres::Client;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LessonTime {
+    pub index: u8, // first lesson is 0, same as in GroupScheduleEntry
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+// bell schedule, maps lesson index to concrete time
+#[derive(Debug, Clone)]
+pub struct LessonTimes {
+    times: Vec<LessonTime>,
+}
+
+impl LessonTime {
+
+    pub fn new(index: u8, start: NaiveTime, end: NaiveTime) -> Self {
+        Self {
+            index,
+            start,
+            end,
+        }
+    }
+}
+
+impl LessonTimes {
+
+    pub fn new(times: Vec<LessonTime>) -> Self {
+        Self {
+            times,
+        }
+    }
+
+    // used when lesson_times table has nothing for the academic year
+    pub fn standard() -> Self {
+        Self::new(vec![
+            LessonTime::new(0, NaiveTime::from_hms(8, 30, 0), NaiveTime::from_hms(10, 10, 0)),
+            LessonTime::new(1, NaiveTime::from_hms(10, 25, 0), NaiveTime::from_hms(12, 5, 0)),
+            LessonTime::new(2, NaiveTime::from_hms(12, 20, 0), NaiveTime::from_hms(14, 0, 0)),
+            LessonTime::new(3, NaiveTime::from_hms(14, 15, 0), NaiveTime::from_hms(15, 55, 0)),
+            LessonTime::new(4, NaiveTime::from_hms(16, 10, 0), NaiveTime::from_hms(18, 15, 0)),
+            LessonTime::new(5, NaiveTime::from_hms(18, 30, 0), NaiveTime::from_hms(20, 0, 0)),
+        ])
+    }
+
+    pub fn for_index(&self, index: u8) -> Option<&LessonTime> {
+        self.times.iter().find(|v| v.index == index)
+    }
+
+    // for sources which give lesson start time instead of its index
+    pub fn by_start(&self, start: NaiveTime) -> Option<&LessonTime> {
+        self.times.iter().find(|v| v.start == start)
+    }
+}
+
+impl Default for LessonTimes {
+
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+pub async fn lesson_times_for_academic_year(database: &Client, academic_year: i32) -> Result<LessonTimes, tokio_postgres::Error> {
+    let rows = database.query(
+        "select lesson_index, start_time, end_time from lesson_times where academic_year = $1 order by lesson_index",
+        &[&academic_year]
+    ).await?;
+
+    if rows.is_empty() {
+        return Ok(LessonTimes::standard());
+    }
+
+    Ok(LessonTimes::new(rows.iter()
+        .map(|row| LessonTime::new(
+            row.get::<&str, i16>("lesson_index") as u8,
+            row.get("start_time"),
+            row.get("end_time")
+        ))
+        .collect()
+    ))
+}
@@ -1,3 +1,11 @@
 pub mod groups;
 pub mod schedule;
 pub mod schedule_queries;
+pub mod lesson_times;
+pub mod academic_calendar;
+pub mod students;
+pub mod lecturers;
+pub mod rooms;
+pub mod schedule_changes;
+pub mod webhooks;
+pub mod schedule_overrides;
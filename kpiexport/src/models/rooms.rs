@@ -0,0 +1,26 @@
+use tokio_postgres::Client;
+use crate::models::schedule::GroupScheduleEntry;
+use crate::models::schedule_queries::schedule_entry_from_row;
+
+// every location mentioned in cached group schedules
+pub async fn all_locations(database: &Client) -> Result<Vec<String>, tokio_postgres::Error> {
+    database.query("select distinct unnest(locations) as location from schedule", &[]).await
+        .map(|v| v.iter().map(|r| r.get("location")).collect())
+}
+
+// locations used by any group at the time slot
+pub async fn locations_at(database: &Client, week: i16, day: i16, index: i16) -> Result<Vec<String>, tokio_postgres::Error> {
+    database.query(
+        "select distinct unnest(locations) as location from schedule where week = $1 and day = $2 and index = $3",
+        &[&week, &day, &index]
+    ).await.map(|v| v.iter().map(|r| r.get("location")).collect())
+}
+
+// entries with a location starting with the room name, paired with the group name. Callers still have to
+// check the location, "7-41" is a prefix of "7-418".
+pub async fn room_schedule_entries(database: &Client, room_name: &str) -> Result<Vec<(String, GroupScheduleEntry)>, tokio_postgres::Error> {
+    database.query(
+        "select * from schedule where exists (select 1 from unnest(locations) as location where trim(location) like $1 || '%') order by week, day, index, group_name",
+        &[&room_name]
+    ).await.map(|v| v.iter().map(|r| (r.get("group_name"), schedule_entry_from_row(r))).collect())
+}
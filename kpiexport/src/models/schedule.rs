@@ -1,11 +1,33 @@
 use std::fmt::Debug;
 use serde::{Serializer, Serialize, Deserialize};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use crate::models::lesson_times::LessonTime;
+use crate::models::schedule_overrides::ScheduleOverrideMarker;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GroupSchedule {
     pub entries: Vec<GroupScheduleEntry>,
     #[serde(skip_serializing)]
     pub source: Option<GroupScheduleSource>,
+    #[serde(skip)]
+    pub updated_at: Option<DateTime<Utc>>, // last time the schedule was saved to the database
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool, // older than SCHEDULE_MAX_AGE_DAYS, served because it could not be fetched yet
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cancelled_entries: Vec<GroupScheduleEntry>, // removed by schedule overrides, only when serving the schedule
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exceptions: Vec<ScheduleException>, // set by schedule overrides limited to dates, only when serving the schedule
+}
+
+// lessons of a time slot on one date, which are different from the weekly entries because of schedule overrides.
+// Entries of the time slot are used on all other dates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleException {
+    pub date: NaiveDate,
+    pub index: u8,
+    pub entries: Vec<GroupScheduleEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cancelled_entries: Vec<GroupScheduleEntry>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,10 +39,22 @@ pub struct GroupScheduleEntry {
     pub index: u8, // first lesson is 0
     pub names: Vec<String>,
     pub lecturers: Vec<String>,
-    pub locations: Vec<String>
+    pub locations: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lesson_type: Option<LectureType>,
+
+    // filled from lesson times (bell schedule) when serving the schedule
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "lesson_time_format")]
+    pub start_time: Option<NaiveTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "lesson_time_format")]
+    pub end_time: Option<NaiveTime>,
+
+    // set when a schedule override added, replaced or cancelled the lesson
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overridden: Option<ScheduleOverrideMarker>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ScheduleDay {
     Monday,
     Tuesday,
@@ -46,6 +80,10 @@ impl GroupScheduleEntry {
             names: Vec::new(),
             lecturers: Vec::new(),
             locations: Vec::new(),
+            lesson_type: None,
+            start_time: None,
+            end_time: None,
+            overridden: None,
         }
     }
 
@@ -70,6 +108,31 @@ impl GroupScheduleEntry {
         }
     }
 
+    pub fn with_lesson_type(self, lesson_type: Option<LectureType>) -> Self {
+        Self {
+            lesson_type,
+            ..self
+        }
+    }
+
+    // rozklad writes lesson type after the room, "7-418 Лек". It is moved from locations to lesson_type.
+    pub fn with_lesson_type_from_locations(self) -> Self {
+        let mut lesson_type = self.lesson_type;
+        let mut locations = Vec::new();
+
+        for location in &self.locations {
+            let (location, location_lesson_type) = LectureType::split_location(location);
+            lesson_type = lesson_type.or(location_lesson_type);
+            locations.push(location);
+        }
+
+        Self {
+            locations,
+            lesson_type,
+            ..self
+        }
+    }
+
     pub fn with_subject_id(self, subject_id: SubjectId) -> Self {
         Self {
             subject_id: Some(subject_id),
@@ -77,6 +140,40 @@ impl GroupScheduleEntry {
         }
     }
 
+    pub fn with_override(self, marker: ScheduleOverrideMarker) -> Self {
+        Self {
+            overridden: Some(marker),
+            ..self
+        }
+    }
+
+    pub fn with_lesson_time(self, lesson_time: &LessonTime) -> Self {
+        Self {
+            start_time: Some(lesson_time.start),
+            end_time: Some(lesson_time.end),
+            ..self
+        }
+    }
+
+    // keeps only the names at given positions. In split lessons lecturers and locations go in the same order
+    // as names, but only if there is one for each name.
+    pub fn with_only_names(self, indexes: &[usize]) -> Self {
+        let names_count = self.names.len();
+        let select = |values: &Vec<String>| if values.len() == names_count {
+            indexes.iter().map(|index| values[*index].clone()).collect()
+        } else {
+            values.clone()
+        };
+
+        let names = select(&self.names);
+        let lecturers = select(&self.lecturers);
+        let locations = select(&self.locations);
+
+        self.with_names(names)
+            .with_lecturers(lecturers)
+            .with_locations(locations)
+    }
+
     pub fn locations(&self) -> &Vec<String> {
         &self.locations
     }
@@ -117,6 +214,20 @@ impl ScheduleDay {
         }
     }
 
+    // "Пн", campus api has "Вв" for tuesday
+    pub fn from_short_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "Пн" => Self::Monday,
+            "Вв" | "Вт" => Self::Tuesday,
+            "Ср" => Self::Wednesday,
+            "Чт" => Self::Thursday,
+            "Пт" => Self::Friday,
+            "Сб" => Self::Saturday,
+            "Нд" => Self::Sunday,
+            _ => return None,
+        })
+    }
+
     pub fn to_index(&self) -> u8 {
         match &self {
             Self::Monday => 0,
@@ -148,13 +259,36 @@ impl <'de> Deserialize<'de> for ScheduleDay {
     }
 }
 
+mod lesson_time_format {
+    use chrono::NaiveTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%H:%M";
+
+    pub fn serialize<S>(time: &Option<NaiveTime>, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match time {
+            Some(time) => serializer.serialize_str(&time.format(FORMAT).to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<NaiveTime>, D::Error> where D: Deserializer<'de> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(time) => NaiveTime::parse_from_str(&time, FORMAT)
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
 impl SubjectId {
     pub fn new(id: i32) -> Self {
         Self(id)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ScheduleWeek {
     First,
     Second,
@@ -200,6 +334,8 @@ impl <'de> Deserialize<'de> for ScheduleWeek {
 pub enum GroupScheduleSource {
     Parser,
     API,
+    Campus,
+    Custom, // imported from xlsx, see custom::importer
 }
 
 impl GroupScheduleSource {
@@ -207,7 +343,9 @@ impl GroupScheduleSource {
     pub fn to_string(&self) -> String {
         match &self {
             Self::Parser => "parser".to_string(),
-            Self::API => "api".to_string()
+            Self::API => "api".to_string(),
+            Self::Campus => "campus".to_string(),
+            Self::Custom => "custom".to_string(),
         }
     }
 
@@ -215,12 +353,15 @@ impl GroupScheduleSource {
         match name {
             "parser" => Some(Self::Parser),
             "api" => Some(Self::API),
+            "campus" => Some(Self::Campus),
+            "custom" => Some(Self::Custom),
             _ => None
         }
     }
 }
 
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LectureType {
     Lecture,
     Practice,
@@ -229,7 +370,6 @@ pub enum LectureType {
 
 impl LectureType {
 
-    #[allow(dead_code)] // used in other crates
     pub fn from_location(location: &str) -> Option<Self> {
         Some(if location.contains("Лек") {
             Self::Lecture
@@ -242,6 +382,46 @@ impl LectureType {
         })
     }
 
+    // "7-418 Лек" is split into "7-418" and lecture. Only the short type names are removed, so room names
+    // like "Лабораторія" stay as they are.
+    pub fn split_location(location: &str) -> (String, Option<Self>) {
+        let mut lesson_type = None;
+        let mut parts = Vec::new();
+
+        for part in location.split_whitespace() {
+            let part_lesson_type = match part {
+                "Лек" => Some(Self::Lecture),
+                "Прак" => Some(Self::Practice),
+                "Лаб" => Some(Self::Lab),
+                _ => None,
+            };
+
+            match part_lesson_type {
+                Some(v) if lesson_type.is_none() => lesson_type = Some(v),
+                _ => parts.push(part),
+            }
+        }
+
+        (parts.join(" "), lesson_type)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match &self {
+            Self::Lecture => "lecture",
+            Self::Practice => "practice",
+            Self::Lab => "lab",
+        }
+    }
+
+    pub fn from_string(name: &str) -> Option<Self> {
+        match name {
+            "lecture" => Some(Self::Lecture),
+            "practice" => Some(Self::Practice),
+            "lab" => Some(Self::Lab),
+            _ => None
+        }
+    }
+
     #[allow(dead_code)] // used in other crates
     pub fn to_human(&self) -> String {
         match &self {
@@ -250,4 +430,27 @@ impl LectureType {
             Self::Practice => "Практика",
         }.to_owned()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lesson_type_is_moved_from_locations() {
+        let entry = GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 0)
+            .with_locations(vec!["7-418 Лек".into(), "Лабораторія 5".into()])
+            .with_lesson_type_from_locations();
+
+        assert_eq!(entry.lesson_type, Some(LectureType::Lecture));
+        assert_eq!(entry.locations, vec!["7-418".to_string(), "Лабораторія 5".to_string()]);
+    }
+
+    #[test]
+    fn lesson_type_is_serialized_in_lowercase() {
+        let entry = GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 0)
+            .with_lesson_type(Some(LectureType::Practice));
+
+        assert!(serde_json::to_string(&entry).unwrap().contains("\"lesson_type\":\"practice\""));
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio_postgres::{Client, GenericClient, Row};
+use crate::errors::PersistenceError;
+use crate::models::schedule::{GroupScheduleEntry, LectureType, ScheduleDay, ScheduleWeek};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleChangeType {
+    Added,
+    Removed,
+    Moved, // same lesson in another time slot, lecturers and rooms may have changed too
+    Changed, // same time slot, but other lecturers, rooms or lesson type
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleChange {
+    #[serde(rename = "type")]
+    pub change_type: ScheduleChangeType,
+    pub before: Option<GroupScheduleEntry>,
+    pub after: Option<GroupScheduleEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedScheduleChange {
+    #[serde(flatten)]
+    pub change: ScheduleChange,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl ScheduleChangeType {
+
+    pub fn as_str(&self) -> &'static str {
+        match &self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Moved => "moved",
+            Self::Changed => "changed",
+        }
+    }
+
+    pub fn from_string(name: &str) -> Option<Self> {
+        match name {
+            "added" => Some(Self::Added),
+            "removed" => Some(Self::Removed),
+            "moved" => Some(Self::Moved),
+            "changed" => Some(Self::Changed),
+            _ => None
+        }
+    }
+}
+
+impl ScheduleChange {
+
+    fn names(&self) -> &Vec<String> {
+        // every change has at least one of the entries
+        &self.after.as_ref().or(self.before.as_ref()).unwrap().names
+    }
+}
+
+// columns for one side of the change, all null if there is no entry
+struct EntryColumns {
+    week: Option<i16>,
+    day: Option<i16>,
+    index: Option<i16>,
+    lecturers: Option<Vec<String>>,
+    locations: Option<Vec<String>>,
+    lesson_type: Option<&'static str>,
+}
+
+impl EntryColumns {
+
+    fn from_entry(entry: &Option<GroupScheduleEntry>) -> Self {
+        Self {
+            week: entry.as_ref().map(|v| v.week.to_index() as i16),
+            day: entry.as_ref().map(|v| v.day.to_index() as i16),
+            index: entry.as_ref().map(|v| v.index as i16),
+            lecturers: entry.as_ref().map(|v| v.lecturers.clone()),
+            locations: entry.as_ref().map(|v| v.locations.clone()),
+            lesson_type: entry.as_ref().and_then(|v| v.lesson_type).map(|v| v.as_str()),
+        }
+    }
+}
+
+const INSERT_SCHEDULE_CHANGE: &str = "insert into schedule_changes (group_name, change_type, names, \
+    old_week, old_day, old_index, old_lecturers, old_locations, old_lesson_type, \
+    new_week, new_day, new_index, new_lecturers, new_locations, new_lesson_type) \
+    values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)";
+
+pub async fn save_schedule_changes<C: GenericClient + Sync>(database: &C, group_name: &str, changes: &[ScheduleChange]) -> Result<(), PersistenceError> {
+    for change in changes {
+        let old = EntryColumns::from_entry(&change.before);
+        let new = EntryColumns::from_entry(&change.after);
+
+        database.execute(INSERT_SCHEDULE_CHANGE, &[
+            &group_name, &change.change_type.as_str(), change.names(),
+            &old.week, &old.day, &old.index, &old.lecturers, &old.locations, &old.lesson_type,
+            &new.week, &new.day, &new.index, &new.lecturers, &new.locations, &new.lesson_type,
+        ]).await?;
+    }
+
+    Ok(())
+}
+
+// oldest first
+pub async fn schedule_changes_since(database: &Client, group_name: &str, since: &DateTime<Utc>) -> Result<Vec<RecordedScheduleChange>, tokio_postgres::Error> {
+    database.query(
+        "select * from schedule_changes where group_name = $1 and detected_at > $2 order by detected_at, id",
+        &[&group_name, since]
+    ).await.map(|v| v.iter().filter_map(recorded_change_from_row).collect())
+}
+
+fn recorded_change_from_row(row: &Row) -> Option<RecordedScheduleChange> {
+    let change_type: String = row.get("change_type");
+    let change_type = match ScheduleChangeType::from_string(&change_type) {
+        Some(v) => v,
+        None => {
+            error!("unknown schedule change type in database: {}", change_type);
+            return None;
+        }
+    };
+
+    Some(RecordedScheduleChange {
+        change: ScheduleChange {
+            change_type,
+            before: entry_from_row(row, "old"),
+            after: entry_from_row(row, "new"),
+        },
+        detected_at: row.get("detected_at"),
+    })
+}
+
+fn entry_from_row(row: &Row, prefix: &str) -> Option<GroupScheduleEntry> {
+    let column = |name: &str| format!("{}_{}", prefix, name);
+
+    let week: i16 = row.get::<&str, Option<i16>>(&column("week"))?;
+    let day: i16 = row.get(column("day").as_str());
+    let index: i16 = row.get(column("index").as_str());
+
+    Some(GroupScheduleEntry::new(ScheduleWeek::from_index(week as u8), ScheduleDay::from_index(day as u8), index as u8)
+        .with_names(row.get("names"))
+        .with_lecturers(row.get(column("lecturers").as_str()))
+        .with_locations(row.get(column("locations").as_str()))
+        .with_lesson_type(row.get::<&str, Option<String>>(&column("lesson_type")).and_then(|v| LectureType::from_string(&v)))
+    )
+}
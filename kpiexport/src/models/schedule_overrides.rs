@@ -0,0 +1,303 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::{Client, Row};
+use crate::models::academic_calendar::AcademicCalendar;
+use crate::models::schedule::{LectureType, ScheduleDay, ScheduleWeek};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleOverrideAction {
+    Add,
+    Cancel,
+    Replace,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleOverride {
+    pub id: i32,
+    pub group_name: String,
+    pub action: ScheduleOverrideAction,
+    pub week: ScheduleWeek,
+    pub day: ScheduleDay,
+    pub index: u8,
+    pub names: Vec<String>,
+    pub lecturers: Vec<String>,
+    pub locations: Vec<String>,
+    pub lesson_type: Option<LectureType>,
+    pub valid_from: Option<NaiveDate>,
+    pub valid_until: Option<NaiveDate>,
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// week, day and index are numbered the same way as in schedule entries, starting from 0
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewScheduleOverride {
+    pub action: ScheduleOverrideAction,
+    pub week: u8,
+    pub day: u8,
+    pub index: u8,
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub lecturers: Vec<String>,
+    #[serde(default)]
+    pub locations: Vec<String>,
+    pub lesson_type: Option<LectureType>,
+    pub valid_from: Option<NaiveDate>,
+    pub valid_until: Option<NaiveDate>,
+    pub comment: Option<String>,
+}
+
+// what is shown on schedule entries changed by an override
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleOverrideMarker {
+    pub id: i32,
+    pub action: ScheduleOverrideAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl ScheduleOverrideAction {
+
+    pub fn as_str(&self) -> &'static str {
+        match &self {
+            Self::Add => "add",
+            Self::Cancel => "cancel",
+            Self::Replace => "replace",
+        }
+    }
+
+    pub fn from_string(name: &str) -> Option<Self> {
+        match name {
+            "add" => Some(Self::Add),
+            "cancel" => Some(Self::Cancel),
+            "replace" => Some(Self::Replace),
+            _ => None
+        }
+    }
+}
+
+impl ScheduleOverride {
+
+    // overrides without dates change the weekly schedule, the rest only change lessons on their dates
+    pub fn is_limited_to_dates(&self) -> bool {
+        self.valid_from.is_some() || self.valid_until.is_some()
+    }
+
+    // dates of from..=until within the override date range with a lesson of the time slot
+    pub fn lesson_dates(&self, calendar: &AcademicCalendar, from: NaiveDate, until: NaiveDate) -> Vec<NaiveDate> {
+        let from = self.valid_from.map_or(from, |v| v.max(from));
+        let until = self.valid_until.map_or(until, |v| v.min(until));
+
+        slot_lesson_dates(&self.week, &self.day, calendar, from, until)
+    }
+
+    pub fn marker(&self) -> ScheduleOverrideMarker {
+        ScheduleOverrideMarker {
+            id: self.id,
+            action: self.action,
+            valid_from: self.valid_from,
+            valid_until: self.valid_until,
+            comment: self.comment.clone(),
+        }
+    }
+}
+
+impl NewScheduleOverride {
+
+    // None if the override can be saved. Dates have to include a day with the lesson of the time slot within
+    // a year from the first (or before the last) of them, otherwise the override would never be applied.
+    pub fn validation_error(&self, calendar: &AcademicCalendar) -> Option<&'static str> {
+        if self.week > 1 || self.day > 6 {
+            return Some("invalid_time_slot");
+        }
+
+        if self.action == ScheduleOverrideAction::Add && self.names.is_empty() {
+            return Some("names_required");
+        }
+
+        let max_search = Duration::days(MAX_LESSON_SEARCH_DAYS);
+        let (from, until) = match (self.valid_from, self.valid_until) {
+            (Some(from), Some(until)) if from > until => return Some("invalid_date_range"),
+            (Some(from), Some(until)) => (from, until.min(from + max_search)),
+            (Some(from), None) => (from, from + max_search),
+            (None, Some(until)) => (until - max_search, until),
+            (None, None) => return None,
+        };
+
+        let week = ScheduleWeek::from_index(self.week);
+        let day = ScheduleDay::from_index(self.day);
+        if slot_lesson_dates(&week, &day, calendar, from, until).is_empty() {
+            return Some("no_lesson_in_date_range");
+        }
+
+        None
+    }
+}
+
+// longer than an academic year, so every term is checked
+const MAX_LESSON_SEARCH_DAYS: i64 = 366;
+
+// dates with a lesson of the time slot: the day of week and week parity match, the date is in a term and is not
+// a holiday or exam session
+fn slot_lesson_dates(week: &ScheduleWeek, day: &ScheduleDay, calendar: &AcademicCalendar, from: NaiveDate, until: NaiveDate) -> Vec<NaiveDate> {
+    let days_till_day = (day.to_index() as i64 - from.weekday().num_days_from_monday() as i64).rem_euclid(7);
+    let mut date = from + Duration::days(days_till_day);
+    let mut dates = Vec::new();
+
+    while date <= until {
+        let term = calendar.term_at(date);
+        if term.contains(date) && term.week(date) == *week && calendar.exclusion_at(date).is_none() {
+            dates.push(date);
+        }
+        date += Duration::days(7);
+    }
+
+    dates
+}
+
+const OVERRIDE_COLUMNS: &str = "id, group_name, action, week, day, index, names, lecturers, locations, lesson_type, valid_from, valid_until, comment, created_at";
+
+fn override_from_row(row: &Row) -> Option<ScheduleOverride> {
+    let action: String = row.get("action");
+    let action = match ScheduleOverrideAction::from_string(&action) {
+        Some(v) => v,
+        None => {
+            error!("unknown schedule override action in database: {}", action);
+            return None;
+        }
+    };
+
+    Some(ScheduleOverride {
+        id: row.get("id"),
+        group_name: row.get("group_name"),
+        action,
+        week: ScheduleWeek::from_index(row.get::<&str, i16>("week") as u8),
+        day: ScheduleDay::from_index(row.get::<&str, i16>("day") as u8),
+        index: row.get::<&str, i16>("index") as u8,
+        names: row.get("names"),
+        lecturers: row.get("lecturers"),
+        locations: row.get("locations"),
+        lesson_type: row.get::<&str, Option<&str>>("lesson_type").and_then(LectureType::from_string),
+        valid_from: row.get("valid_from"),
+        valid_until: row.get("valid_until"),
+        comment: row.get("comment"),
+        created_at: row.get("created_at"),
+    })
+}
+
+pub async fn add_schedule_override(database: &Client, group_name: &str, new_override: &NewScheduleOverride) -> Result<Option<ScheduleOverride>, tokio_postgres::Error> {
+    let week = new_override.week as i16;
+    let day = new_override.day as i16;
+    let index = new_override.index as i16;
+    let lesson_type = new_override.lesson_type.map(|v| v.as_str());
+
+    database.query_one(
+        &format!(
+            "insert into schedule_overrides \
+                (group_name, action, week, day, index, names, lecturers, locations, lesson_type, valid_from, valid_until, comment) \
+                values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) returning {}",
+            OVERRIDE_COLUMNS
+        ),
+        &[
+            &group_name, &new_override.action.as_str(), &week, &day, &index, &new_override.names, &new_override.lecturers,
+            &new_override.locations, &lesson_type, &new_override.valid_from, &new_override.valid_until, &new_override.comment,
+        ]
+    ).await.map(|v| override_from_row(&v))
+}
+
+pub async fn schedule_overrides_for_group(database: &Client, group_name: &str) -> Result<Vec<ScheduleOverride>, tokio_postgres::Error> {
+    database.query(
+        &format!("select {} from schedule_overrides where group_name = $1 order by id", OVERRIDE_COLUMNS),
+        &[&group_name]
+    ).await.map(|v| v.iter().filter_map(override_from_row).collect())
+}
+
+// overrides with a date range which overlaps from..=until, in the order they were added
+pub async fn active_schedule_overrides(database: &Client, group_name: &str, from: NaiveDate, until: NaiveDate) -> Result<Vec<ScheduleOverride>, tokio_postgres::Error> {
+    database.query(
+        &format!(
+            "select {} from schedule_overrides where group_name = $1 \
+                and (valid_from is null or valid_from <= $3) and (valid_until is null or valid_until >= $2) order by id",
+            OVERRIDE_COLUMNS
+        ),
+        &[&group_name, &from, &until]
+    ).await.map(|v| v.iter().filter_map(override_from_row).collect())
+}
+
+// false if the group has no such override
+pub async fn remove_schedule_override(database: &Client, group_name: &str, id: i32) -> Result<bool, tokio_postgres::Error> {
+    database.execute("delete from schedule_overrides where group_name = $1 and id = $2", &[&group_name, &id]).await
+        .map(|v| v > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::academic_calendar::{AcademicTerm, CalendarExclusion, ExclusionKind, Term};
+
+    fn new_override(day: u8, from: NaiveDate, until: NaiveDate) -> NewScheduleOverride {
+        NewScheduleOverride {
+            action: ScheduleOverrideAction::Cancel,
+            week: 0,
+            day,
+            index: 0,
+            names: Vec::new(),
+            lecturers: Vec::new(),
+            locations: Vec::new(),
+            lesson_type: None,
+            valid_from: Some(from),
+            valid_until: Some(until),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn date_range_has_to_include_the_lesson() {
+        let calendar = AcademicCalendar::new(
+            vec![AcademicTerm {
+                academic_year: 2021,
+                term: Term::First,
+                starts_on: NaiveDate::from_ymd(2021, 9, 1),
+                ends_on: NaiveDate::from_ymd(2021, 12, 24),
+                first_week_starts_on: NaiveDate::from_ymd(2021, 8, 30),
+            }],
+            Vec::new()
+        );
+        let monday_of_second_week = NaiveDate::from_ymd(2021, 10, 4);
+        let monday_of_first_week = NaiveDate::from_ymd(2021, 10, 11);
+
+        assert_eq!(new_override(0, monday_of_second_week, monday_of_second_week).validation_error(&calendar), Some("no_lesson_in_date_range"));
+        assert_eq!(new_override(1, monday_of_first_week, monday_of_first_week).validation_error(&calendar), Some("no_lesson_in_date_range"));
+        assert_eq!(new_override(0, monday_of_first_week, monday_of_first_week).validation_error(&calendar), None);
+        assert_eq!(new_override(0, monday_of_second_week, monday_of_first_week).validation_error(&calendar), None);
+        assert_eq!(new_override(0, monday_of_first_week, monday_of_second_week).validation_error(&calendar), Some("invalid_date_range"));
+
+        // the first monday of the first week is a holiday, the next one is after the end of the term
+        let holiday_calendar = AcademicCalendar::new(
+            vec![AcademicTerm {
+                ends_on: NaiveDate::from_ymd(2021, 10, 20),
+                ..calendar.term_at(monday_of_first_week)
+            }],
+            vec![CalendarExclusion {
+                starts_on: monday_of_first_week,
+                ends_on: monday_of_first_week,
+                kind: ExclusionKind::Holiday,
+                description: "test holiday".into(),
+            }]
+        );
+        let monday_after_term = NaiveDate::from_ymd(2021, 10, 25);
+        assert_eq!(new_override(0, monday_of_first_week, monday_after_term).validation_error(&holiday_calendar), Some("no_lesson_in_date_range"));
+
+        // lessons are only looked for within a year from the start of the range
+        let from = NaiveDate::from_ymd(1, 1, 1);
+        let until = NaiveDate::from_ymd(9999, 12, 31);
+        assert_eq!(new_override(0, from, until).validation_error(&calendar), None);
+        assert_eq!(new_override(0, until, until).validation_error(&calendar), Some("no_lesson_in_date_range"));
+    }
+}
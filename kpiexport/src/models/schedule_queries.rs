@@ -1,5 +1,9 @@
-use tokio_postgres::{Client, Transaction};
-use crate::models::schedule::GroupSchedule;
+use std::pin::pin;
+use chrono::{DateTime, Utc};
+use tokio_postgres::{Client, GenericClient, Row};
+use tokio_postgres::binary_copy::BinaryCopyInWriter;
+use tokio_postgres::types::Type;
+use crate::models::schedule::{GroupSchedule, GroupScheduleEntry, GroupScheduleSource, LectureType, ScheduleDay, ScheduleWeek};
 use crate::errors::PersistenceError;
 
 pub async fn api_groups_to_refresh(database: &Client) -> Result<Vec<String>, tokio_postgres::Error> {
@@ -7,51 +11,110 @@ pub async fn api_groups_to_refresh(database: &Client) -> Result<Vec<String>, tok
         .map(|v| v.iter().map(|r| r.get("group_name")).collect())
 }
 
+// imported (custom) schedules are not on rozklad, they are only replaced by another import
 pub async fn groups_with_old_schedule(database: &Client, hours_diff: i64) -> Result<Vec<String>, tokio_postgres::Error> {
-    database.query("select distinct group_name from schedule where updated_at <= date_trunc('day', NOW() - cast($1 as interval)) limit 10", &[&format!("{} hours", hours_diff)]).await
+    database.query("select distinct group_name from schedule where updated_at <= date_trunc('day', NOW() - cast($1 as interval)) and source <> 'custom' limit 10", &[&format!("{} hours", hours_diff)]).await
         .map(|v| v.iter().map(|r| r.get("group_name")).collect())
 }
 
-pub async fn remove_old_schedule_from_database(database: &tokio_postgres::Client, group_name: &str) -> Result<(), tokio_postgres::Error> {
-    database.execute("delete from schedule where group_name = $1", &[&group_name]).await.map(|_v| ())
+// current schedule regardless of its age, to compare it with the new one
+pub async fn group_schedule_entries<C: GenericClient + Sync>(database: &C, group_name: &str) -> Result<Vec<GroupScheduleEntry>, PersistenceError> {
+    Ok(database.query("select * from schedule where group_name = $1", &[&group_name]).await?
+        .iter()
+        .map(schedule_entry_from_row)
+        .collect())
 }
 
-pub async fn remove_old_schedule_from_database_transaction(database: &Transaction<'_>, group_name: &str) -> Result<(), tokio_postgres::Error> {
-    database.execute("delete from schedule where group_name = $1", &[&group_name]).await.map(|_v| ())
-}
+// old schedule is deleted only together with saving the new one, so a failed save (or fetch before it) leaves the
+// group with the schedule it had. Runs in a savepoint when the client is a transaction.
+pub async fn replace_group_schedule<C: GenericClient>(database: &mut C, group_name: &str, schedule: &GroupSchedule) -> Result<(), PersistenceError> {
+    let source = match &schedule.source {
+        Some(v) => v.to_string(),
+        None => {
+            error!("schedule for {} has no source", group_name);
+            return Err(PersistenceError::FailedToSave);
+        }
+    };
+
+    let transaction = database.transaction().await?;
+    transaction.execute("delete from schedule where group_name = $1", &[&group_name]).await?;
+
+    let sink = transaction.copy_in("copy schedule (group_name, source, week, day, index, names, lecturers, locations, lesson_type) from stdin binary").await?;
+    let writer = BinaryCopyInWriter::new(sink, &[
+        Type::TEXT, Type::TEXT, Type::INT2, Type::INT2, Type::INT2, Type::TEXT_ARRAY, Type::TEXT_ARRAY, Type::TEXT_ARRAY, Type::TEXT,
+    ]);
+    let mut writer = pin!(writer);
 
-//noinspection DuplicatedCode
-pub async fn save_schedule_to_database(database: &tokio_postgres::Client, group_name: &str, schedule: &GroupSchedule) -> Result<(), PersistenceError> {
     for entry in &schedule.entries {
         let week_index: i16 = entry.week.to_index() as i16;
         let day_index: i16 = entry.day.to_index() as i16;
         let index: i16 = entry.index as i16;
+        let lesson_type: Option<&str> = entry.lesson_type.map(|v| v.as_str());
 
-        if let Err(err) = database.execute(
-            "insert into schedule (group_name, source, week, day, index, names, lecturers, locations) values ($1, $2, $3, $4, $5, $6, $7, $8)",
-            &[&group_name, &schedule.source.as_ref().unwrap().to_string(), &week_index, &day_index, &index, &entry.names, &entry.lecturers, &entry.locations]
-        ).await {
-            error!("failed to save entry to database: {}", err);
-        }
+        writer.as_mut().write(&[
+            &group_name, &source, &week_index, &day_index, &index, &entry.names, &entry.lecturers, &entry.locations, &lesson_type,
+        ]).await?;
     }
 
+    writer.finish().await?;
+    transaction.commit().await?;
+
     Ok(())
 }
 
-//noinspection DuplicatedCode
-pub async fn save_schedule_to_database_transaction(database: &Transaction<'_>, group_name: &str, schedule: &GroupSchedule) -> Result<(), PersistenceError> {
-    for entry in &schedule.entries {
-        let week_index: i16 = entry.week.to_index() as i16;
-        let day_index: i16 = entry.day.to_index() as i16;
-        let index: i16 = entry.index as i16;
+// web servers listen on this channel and drop cached schedules of the group in the payload
+pub const SCHEDULE_UPDATES_CHANNEL: &str = "schedule_updates";
+
+// delivered when the transaction is committed
+pub async fn notify_schedule_updated<C: GenericClient + Sync>(database: &C, group_name: &str) -> Result<(), PersistenceError> {
+    database.execute("select pg_notify($1, $2)", &[&SCHEDULE_UPDATES_CHANNEL, &group_name]).await?;
+    Ok(())
+}
+
+// schedule is returned however old it is, but marked as stale when it was saved more than max_age ago
+pub async fn load_group_schedule_from_database(database: &Client, group_name: &str, max_age: chrono::Duration) -> Result<Option<GroupSchedule>, PersistenceError> {
+    let res = match database.query("select * from schedule where group_name = $1", &[&group_name]).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("failed to execute database query: {}", err);
+            return Err(PersistenceError::FailedToLoad);
+        }
+    };
+
+    let mut source: Option<GroupScheduleSource> = None;
+    let mut entries: Vec<GroupScheduleEntry> = vec![];
+    let mut updated_at: Option<DateTime<Utc>> = None;
+
+    for row in res {
+        entries.push(schedule_entry_from_row(&row));
+
+        let row_updated_at: DateTime<Utc> = row.get("updated_at");
+        updated_at = updated_at.max(Some(row_updated_at));
 
-        if let Err(err) = database.execute(
-            "insert into schedule (group_name, source, week, day, index, names, lecturers, locations) values ($1, $2, $3, $4, $5, $6, $7, $8)",
-            &[&group_name, &schedule.source.as_ref().unwrap().to_string(), &week_index, &day_index, &index, &entry.names, &entry.lecturers, &entry.locations]
-        ).await {
-            error!("failed to save entry to database: {}", err);
+        if source.is_none() {
+            let source_str: String = row.get("source");
+
+            source = GroupScheduleSource::from_string(&source_str);
+            if source.is_none() {
+                error!("unknown from group schedule source in database: {}", &source_str);
+            }
         }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    // nothing to refresh imported schedules from
+    let stale = !matches!(source, Some(GroupScheduleSource::Custom)) && updated_at.is_some_and(|v| v < Utc::now() - max_age);
+
+    Ok(source.map(|source| GroupSchedule { source: Some(source), entries, updated_at, stale, cancelled_entries: Vec::new(), exceptions: Vec::new() }))
+}
+
+pub fn schedule_entry_from_row(row: &Row) -> GroupScheduleEntry {
+    GroupScheduleEntry::new(
+            ScheduleWeek::from_index(row.get::<&str, i16>("week") as u8),
+            ScheduleDay::from_index(row.get::<&str, i16>("day") as u8),
+            row.get::<&str, i16>("index") as u8
+        )
+        .with_names(row.get("names"))
+        .with_lecturers(row.get("lecturers"))
+        .with_locations(row.get("locations"))
+        .with_lesson_type(row.get::<&str, Option<String>>("lesson_type").and_then(|v| LectureType::from_string(&v)))
+}
@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+use tokio_postgres::Client;
+
+// subjects a student attends when the group is split into subgroups or chooses electives
+#[derive(Debug, Clone)]
+pub struct StudentSubjects {
+    pub student_name: String,
+    pub subjects: Vec<String>,
+}
+
+pub async fn student_subjects_for_group(database: &Client, group_name: &str) -> Result<Vec<StudentSubjects>, tokio_postgres::Error> {
+    let rows = database.query(
+        "select student_name, subject_name from student_subjects where group_name = $1",
+        &[&group_name]
+    ).await?;
+
+    let mut subjects_by_student: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for row in rows {
+        subjects_by_student.entry(row.get("student_name"))
+            .or_default()
+            .push(row.get("subject_name"));
+    }
+
+    Ok(subjects_by_student.into_iter()
+        .map(|(student_name, subjects)| StudentSubjects { student_name, subjects })
+        .collect())
+}
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio_postgres::{Client, GenericClient, Row};
+use crate::errors::PersistenceError;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookSubscription {
+    pub id: i32,
+    pub url: String,
+    pub group_names: Vec<String>, // empty for every group
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub url: String,
+    pub secret: String,
+    pub payload: String,
+    pub attempts: i32, // before the current one
+}
+
+fn subscription_from_row(row: &Row) -> WebhookSubscription {
+    WebhookSubscription {
+        id: row.get("id"),
+        url: row.get("url"),
+        group_names: row.get("group_names"),
+        created_at: row.get("created_at"),
+    }
+}
+
+pub async fn add_webhook_subscription(database: &Client, url: &str, group_names: &[String], secret: &str) -> Result<WebhookSubscription, tokio_postgres::Error> {
+    database.query_one(
+        "insert into webhook_subscriptions (url, group_names, secret) values ($1, $2, $3) returning id, url, group_names, created_at",
+        &[&url, &group_names, &secret]
+    ).await.map(|v| subscription_from_row(&v))
+}
+
+pub async fn all_webhook_subscriptions(database: &Client) -> Result<Vec<WebhookSubscription>, tokio_postgres::Error> {
+    database.query("select id, url, group_names, created_at from webhook_subscriptions order by id", &[]).await
+        .map(|v| v.iter().map(subscription_from_row).collect())
+}
+
+// false if there was no such subscription
+pub async fn remove_webhook_subscription(database: &Client, id: i32) -> Result<bool, tokio_postgres::Error> {
+    database.execute("delete from webhook_subscriptions where id = $1", &[&id]).await
+        .map(|v| v > 0)
+}
+
+// one delivery for every subscription interested in the group, returns how many were added
+pub async fn enqueue_webhook_deliveries<C: GenericClient + Sync>(database: &C, group_name: &str, payload: &str) -> Result<u64, PersistenceError> {
+    Ok(database.execute(
+        "insert into webhook_deliveries (subscription_id, group_name, payload) \
+            select id, $1, $2 from webhook_subscriptions where cardinality(group_names) = 0 or $1 = any(group_names)",
+        &[&group_name, &payload]
+    ).await?)
+}
+
+// pending deliveries which are due are postponed by lease_seconds, so that other web servers and jobs do not
+// send them at the same time. The attempt result replaces next_attempt_at.
+pub async fn claim_due_webhook_deliveries(database: &Client, limit: i64, lease_seconds: i64) -> Result<Vec<WebhookDelivery>, tokio_postgres::Error> {
+    database.query(
+        "update webhook_deliveries as d set next_attempt_at = now() + $2::text::interval \
+            from webhook_subscriptions as s \
+            where s.id = d.subscription_id and d.id in ( \
+                select id from webhook_deliveries where status = 'pending' and next_attempt_at <= now() \
+                order by next_attempt_at limit $1 for update skip locked \
+            ) \
+            returning d.id, s.url, s.secret, d.payload, d.attempts",
+        &[&limit, &format!("{} seconds", lease_seconds)]
+    ).await.map(|v| v.iter().map(|r| WebhookDelivery {
+        id: r.get("id"),
+        url: r.get("url"),
+        secret: r.get("secret"),
+        payload: r.get("payload"),
+        attempts: r.get("attempts"),
+    }).collect())
+}
+
+// next_attempt_at is None when the delivery succeeded or there are no attempts left
+pub async fn record_webhook_delivery_attempt(
+    database: &Client,
+    delivery_id: i32,
+    response_status: Option<i32>,
+    error: Option<&str>,
+    delivered: bool,
+    next_attempt_at: Option<DateTime<Utc>>
+) -> Result<(), tokio_postgres::Error> {
+    let status = if delivered {
+        "delivered"
+    } else if next_attempt_at.is_some() {
+        "pending"
+    } else {
+        "failed"
+    };
+
+    database.execute(
+        "insert into webhook_delivery_attempts (delivery_id, response_status, error) values ($1, $2, $3)",
+        &[&delivery_id, &response_status, &error]
+    ).await?;
+
+    database.execute(
+        "update webhook_deliveries set attempts = attempts + 1, status = $2, next_attempt_at = coalesce($3, next_attempt_at) where id = $1",
+        &[&delivery_id, &status, &next_attempt_at]
+    ).await.map(|_| ())
+}
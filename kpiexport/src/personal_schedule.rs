@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+use crate::matching::is_same_person_name;
+use crate::models::schedule::GroupSchedule;
+use crate::models::students::StudentSubjects;
+
+#[derive(Debug, PartialEq)]
+pub enum StudentLookupError {
+    NotFound,
+    Ambiguous,
+}
+
+// last name can be entered in latin or cyrillic, with or without first name
+pub fn find_student<'a>(students: &'a [StudentSubjects], last_name: &str) -> Result<&'a StudentSubjects, StudentLookupError> {
+    let matching: Vec<&StudentSubjects> = students.iter()
+        .filter(|student| is_same_person_name(&student.student_name, last_name) || student.student_name
+            .split_whitespace()
+            .next()
+            .map(|student_last_name| is_same_person_name(student_last_name, last_name))
+            .unwrap_or(false)
+        )
+        .collect();
+
+    match matching.len() {
+        0 => Err(StudentLookupError::NotFound),
+        1 => Ok(matching[0]),
+        _ => Err(StudentLookupError::Ambiguous),
+    }
+}
+
+// keeps only the lessons student attends. Subjects nobody in the group is mapped to are attended by everyone.
+pub fn schedule_for_student(schedule: GroupSchedule, group_students: &[StudentSubjects], student: &StudentSubjects) -> GroupSchedule {
+    let optional_subjects: HashSet<&str> = group_students.iter()
+        .flat_map(|v| v.subjects.iter().map(|v| v.trim()))
+        .collect();
+    let attended_subjects: HashSet<&str> = student.subjects.iter()
+        .map(|v| v.trim())
+        .collect();
+
+    let entries = schedule.entries.into_iter()
+        .filter_map(|entry| {
+            let attended: Vec<usize> = entry.names.iter()
+                .enumerate()
+                .filter(|(_, name)| !optional_subjects.contains(name.trim()) || attended_subjects.contains(name.trim()))
+                .map(|(index, _)| index)
+                .collect();
+
+            if attended.is_empty() {
+                None
+            } else if attended.len() == entry.names.len() {
+                Some(entry)
+            } else {
+                Some(entry.with_only_names(&attended))
+            }
+        })
+        .collect();
+
+    GroupSchedule {
+        entries,
+        ..schedule
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schedule::{GroupScheduleEntry, GroupScheduleSource, ScheduleDay, ScheduleWeek};
+
+    fn students() -> Vec<StudentSubjects> {
+        vec![
+            StudentSubjects {
+                student_name: "Волобуєв Микита".into(),
+                subjects: vec!["Англійська мова (група 1)".into()],
+            },
+            StudentSubjects {
+                student_name: "Шевченко Тарас".into(),
+                subjects: vec!["Англійська мова (група 2)".into()],
+            },
+        ]
+    }
+
+    #[test]
+    fn student_is_found_by_transliterated_last_name() {
+        let students = students();
+
+        assert_eq!(find_student(&students, "volobuev").unwrap().student_name, "Волобуєв Микита");
+        assert_eq!(find_student(&students, "Шевченко").unwrap().student_name, "Шевченко Тарас");
+        assert_eq!(find_student(&students, "Franko").unwrap_err(), StudentLookupError::NotFound);
+    }
+
+    #[test]
+    fn split_lessons_are_filtered() {
+        let students = students();
+        let schedule = GroupSchedule {
+            entries: vec![
+                GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 0)
+                    .with_names(vec!["Англійська мова (група 1)".into(), "Англійська мова (група 2)".into()])
+                    .with_lecturers(vec!["Іваненко І. І.".into(), "Петренко П. П.".into()])
+                    .with_locations(vec!["7-418".into()]),
+                GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 1)
+                    .with_names(vec!["Англійська мова (група 2)".into()]),
+                GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 2)
+                    .with_names(vec!["Бази даних".into()]),
+            ],
+            source: Some(GroupScheduleSource::Parser),
+            updated_at: None,
+            stale: false,
+            cancelled_entries: Vec::new(),
+            exceptions: Vec::new(),
+        };
+
+        let schedule = schedule_for_student(schedule, &students, &students[0]);
+
+        assert_eq!(schedule.entries.len(), 2);
+        assert_eq!(schedule.entries[0].names, vec!["Англійська мова (група 1)".to_string()]);
+        assert_eq!(schedule.entries[0].lecturers, vec!["Іваненко І. І.".to_string()]);
+        assert_eq!(schedule.entries[0].locations, vec!["7-418".to_string()]);
+        assert_eq!(schedule.entries[1].names, vec!["Бази даних".to_string()]);
+    }
+}
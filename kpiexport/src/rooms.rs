@@ -0,0 +1,150 @@
+use std::collections::BTreeSet;
+use crate::merged_schedule::{merge_group_entries, MergedScheduleEntry};
+use crate::models::schedule::{GroupScheduleEntry, LectureType};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Room {
+    pub name: String, // building and room, "7-418"
+    pub building: String,
+    pub room: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomLocation {
+    pub room: Room,
+    pub lesson_type: Option<LectureType>,
+}
+
+// "7-418 Лек" is a lecture in room 418 of building 7. Locations without a room ("online", addresses) are skipped.
+pub fn parse_location(location: &str) -> Option<RoomLocation> {
+    let (location, lesson_type) = LectureType::split_location(location);
+    let name = location.split_whitespace().next()?;
+    let (building, room) = name.split_once('-')?;
+
+    if building.is_empty() || room.is_empty() || !building.chars().chain(room.chars()).all(char::is_alphanumeric) {
+        return None;
+    }
+
+    Some(RoomLocation {
+        room: Room {
+            name: name.to_string(),
+            building: building.to_string(),
+            room: room.to_string(),
+        },
+        lesson_type,
+    })
+}
+
+// rooms known from any schedule which are not used in the time slot
+pub fn free_rooms(all_locations: &[String], occupied_locations: &[String], building: Option<&str>) -> Vec<Room> {
+    let occupied: BTreeSet<Room> = occupied_locations.iter()
+        .filter_map(|v| parse_location(v))
+        .map(|v| v.room)
+        .collect();
+
+    let mut rooms: Vec<Room> = all_locations.iter()
+        .filter_map(|v| parse_location(v))
+        .map(|v| v.room)
+        .filter(|v| building.map(|building| v.building == building).unwrap_or(true))
+        .filter(|v| !occupied.contains(v))
+        .collect::<BTreeSet<Room>>()
+        .into_iter()
+        .collect();
+
+    // building 7 goes before building 18
+    rooms.sort_by_key(|v| (v.building.parse::<u32>().unwrap_or(u32::MAX), v.building.clone(), v.room.clone()));
+    rooms
+}
+
+pub fn room_schedule(room: &Room, group_entries: Vec<(String, GroupScheduleEntry)>) -> Vec<MergedScheduleEntry> {
+    merge_group_entries(group_entries.into_iter()
+        .filter_map(|(group_name, entry)| only_room_lessons(room, entry).map(|entry| (group_name, entry)))
+        .collect()
+    )
+}
+
+// split lessons have a location for each name, only names in the room are kept. Entries stored before the
+// lesson type was moved out of locations get it from the room location.
+fn only_room_lessons(room: &Room, entry: GroupScheduleEntry) -> Option<GroupScheduleEntry> {
+    let in_room: Vec<(usize, Option<LectureType>)> = entry.locations.iter()
+        .enumerate()
+        .filter_map(|(index, location)| parse_location(location)
+            .filter(|v| &v.room == room)
+            .map(|v| (index, v.lesson_type))
+        )
+        .collect();
+
+    if in_room.is_empty() {
+        return None;
+    }
+
+    let lesson_type = entry.lesson_type.or_else(|| in_room.iter().find_map(|(_, lesson_type)| *lesson_type));
+    let entry = if entry.names.len() > 1 && entry.locations.len() == entry.names.len() {
+        let indexes: Vec<usize> = in_room.iter().map(|(index, _)| *index).collect();
+        entry.with_only_names(&indexes)
+    } else {
+        entry
+    };
+
+    Some(entry.with_lesson_type(lesson_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schedule::{ScheduleDay, ScheduleWeek};
+
+    #[test]
+    fn locations_are_parsed() {
+        let location = parse_location("7-418 Лек").unwrap();
+        assert_eq!(location.room.building, "7");
+        assert_eq!(location.room.room, "418");
+        assert_eq!(location.lesson_type, Some(LectureType::Lecture));
+
+        assert_eq!(parse_location("18-1005а").unwrap().lesson_type, None);
+        assert!(parse_location("online").is_none());
+        assert!(parse_location("вул. Політехнічна, 41").is_none());
+    }
+
+    #[test]
+    fn occupied_rooms_are_not_free() {
+        let all_locations: Vec<String> = vec!["18-1005 Прак".into(), "7-418 Лек".into(), "7-418".into(), "7-301 Лаб".into()];
+        let occupied: Vec<String> = vec!["7-301 Лаб".into()];
+
+        let rooms = free_rooms(&all_locations, &occupied, None);
+        assert_eq!(rooms.iter().map(|v| v.name.as_str()).collect::<Vec<&str>>(), vec!["7-418", "18-1005"]);
+
+        let rooms = free_rooms(&all_locations, &occupied, Some("18"));
+        assert_eq!(rooms.iter().map(|v| v.name.as_str()).collect::<Vec<&str>>(), vec!["18-1005"]);
+    }
+
+    #[test]
+    fn room_schedule_skips_other_rooms() {
+        let room = parse_location("7-41").unwrap().room;
+        let schedule = room_schedule(&room, vec![
+            ("ІП-82".into(), GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 0)
+                .with_names(vec!["Бази даних".into()])
+                .with_locations(vec!["7-418 Лек".into()])),
+            ("ІП-82".into(), GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 1)
+                .with_names(vec!["Англійська мова (група 1)".into(), "Англійська мова (група 2)".into()])
+                .with_locations(vec!["7-41 Прак".into(), "7-42 Прак".into()])),
+        ]);
+
+        assert_eq!(schedule.len(), 1);
+        assert_eq!(schedule[0].entry.names, vec!["Англійська мова (група 1)".to_string()]);
+        assert_eq!(schedule[0].entry.lesson_type, Some(LectureType::Practice));
+    }
+
+    #[test]
+    fn room_schedule_keeps_structured_lesson_type() {
+        let room = parse_location("7-418").unwrap().room;
+        let schedule = room_schedule(&room, vec![
+            ("ІП-82".into(), GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 0)
+                .with_names(vec!["Бази даних".into()])
+                .with_locations(vec!["7-418".into()])
+                .with_lesson_type(Some(LectureType::Lab))),
+        ]);
+
+        assert_eq!(schedule[0].entry.lesson_type, Some(LectureType::Lab));
+    }
+}
@@ -1,26 +1,297 @@
-use crate::rozklad_parser;
-use crate::rozklad_api;
-use crate::models::schedule::*;
+use std::time::Duration;
+use prometheus::{IntCounterVec, IntGaugeVec, register_int_counter_vec, register_int_gauge_vec, opts};
+use crate::circuit_breaker::CircuitBreaker;
+use crate::campus_api::CampusApi;
+use crate::config::{kpi_campus_api_url, rozklad_api_url, rozklad_url, schedule_source_failure_threshold, schedule_source_retry_after, schedule_sources};
 use crate::errors::RozkladParseError;
-use crate::rozklad_parser::Term;
+use crate::models::academic_calendar::Term;
+use crate::models::schedule::GroupSchedule;
+use crate::rozklad_api::RozkladApi;
+use crate::rozklad_parser::RozkladParser;
+use crate::schedule_source::ScheduleSource;
 
-// get schedule by group name
-pub async fn group_schedule_by_name(client: &reqwest::Client, term: &Term, name: &str) -> Result<GroupSchedule, RozkladParseError> {
-    match rozklad_parser::group_id_by_name(&client, &name).await {
-        Ok(id) => {
-            info!("group id is: {}", id);
+lazy_static! {
+    static ref SOURCE_REQUESTS: IntCounterVec = register_int_counter_vec!(opts!(
+        "kpiexport_schedule_source_requests",
+        "Schedule source requests by result: ok, not_found (or not supported by the source), failed or skipped (circuit is open)"
+    ), &["source", "result"]).unwrap();
+    static ref SOURCE_CIRCUIT_OPEN: IntGaugeVec = register_int_gauge_vec!(opts!(
+        "kpiexport_schedule_source_circuit_open",
+        "1 while a schedule source is skipped after repeated failures"
+    ), &["source"]).unwrap();
+}
+
+struct TrackedSource {
+    source: Box<dyn ScheduleSource>,
+    circuit: CircuitBreaker,
+}
+
+// sources are tried in order until one of them has the schedule. A source which keeps failing is skipped for a
+// while, so that requests do not wait for its timeouts every time.
+pub struct ScheduleSources {
+    sources: Vec<TrackedSource>,
+}
+
+impl ScheduleSources {
+
+    pub fn new(sources: Vec<Box<dyn ScheduleSource>>, failure_threshold: u32, retry_after: Duration) -> Self {
+        Self {
+            sources: sources.into_iter()
+                .map(|source| TrackedSource {
+                    source,
+                    circuit: CircuitBreaker::new(failure_threshold, retry_after),
+                })
+                .collect(),
+        }
+    }
+
+    // SCHEDULE_SOURCES, unknown names are skipped
+    pub fn from_config() -> Self {
+        let sources = schedule_sources().iter()
+            .filter_map(|name| source_by_name(name))
+            .collect();
+
+        Self::new(sources, schedule_source_failure_threshold(), schedule_source_retry_after())
+    }
+
+    pub async fn group_schedule(&self, client: &reqwest::Client, term: &Term, group_name: &str) -> Result<GroupSchedule, RozkladParseError> {
+        let mut last_error = None;
+
+        for tracked in &self.sources {
+            if !Self::allows_request(tracked) {
+                continue;
+            }
+
+            match tracked.source.group_schedule(client, term, group_name).await {
+                Ok(schedule) => {
+                    Self::record_result(tracked, None);
+                    return Ok(schedule);
+                },
+                Err(err) => {
+                    warn!("failed to get schedule of {} from {}: {}", group_name, tracked.source.name(), err);
+                    Self::record_result(tracked, Some(&err));
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(RozkladParseError::NoSourceAvailable))
+    }
 
-            match rozklad_parser::group_schedule(&client, term, &id).await.map(|v| v.0) { 
-                Ok(schedule) => Ok(schedule),
+    // empty if none of the sources could list groups
+    pub async fn groups(&self, client: &reqwest::Client) -> Vec<String> {
+        for tracked in &self.sources {
+            if !Self::allows_request(tracked) {
+                continue;
+            }
+
+            match tracked.source.groups(client).await {
+                Ok(groups) => {
+                    Self::record_result(tracked, None);
+                    return groups;
+                },
+                // still recorded, the request may have been a trial one of a half-open circuit
+                Err(err @ RozkladParseError::SourceUnsupported) => Self::record_result(tracked, Some(&err)),
                 Err(err) => {
-                    error!("failed to get rozklad using parser: {}", err);
-                    rozklad_api::group_schedule(&client, &name).await
+                    warn!("failed to get groups from {}: {}", tracked.source.name(), err);
+                    Self::record_result(tracked, Some(&err));
                 }
             }
         }
-        Err(err) => {
-            error!("failed to get rozklad using parser: {}", err);
-            rozklad_api::group_schedule(&client, &name).await
+
+        Vec::new()
+    }
+
+    fn allows_request(tracked: &TrackedSource) -> bool {
+        let allowed = tracked.circuit.allows_request();
+        if !allowed {
+            SOURCE_REQUESTS.with_label_values(&[tracked.source.name(), "skipped"]).inc();
+        }
+        allowed
+    }
+
+    fn record_result(tracked: &TrackedSource, error: Option<&RozkladParseError>) {
+        let result = match error {
+            None => "ok",
+            Some(err) if !err.is_source_failure() => "not_found",
+            Some(_) => "failed",
+        };
+
+        if result == "failed" {
+            tracked.circuit.record_failure();
+        } else {
+            tracked.circuit.record_success();
+        }
+
+        let name = tracked.source.name();
+        SOURCE_REQUESTS.with_label_values(&[name, result]).inc();
+        SOURCE_CIRCUIT_OPEN.with_label_values(&[name]).set(tracked.circuit.is_open() as i64);
+    }
+}
+
+fn source_by_name(name: &str) -> Option<Box<dyn ScheduleSource>> {
+    match name {
+        "parser" => Some(Box::new(RozkladParser::new(rozklad_url()))),
+        "api" => Some(Box::new(RozkladApi::new(rozklad_api_url()))),
+        "campus" => Some(Box::new(CampusApi::new(kpi_campus_api_url()))),
+        other => {
+            error!("unknown schedule source: {}", other);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use crate::models::schedule::GroupScheduleSource;
+    use crate::rozklad_mock::start_rozklad_mock;
+
+    // fails with the given error, counts calls
+    struct FailingSource {
+        error: fn() -> RozkladParseError,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ScheduleSource for FailingSource {
+
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn groups(&self, _client: &reqwest::Client) -> Result<Vec<String>, RozkladParseError> {
+            Err(RozkladParseError::SourceUnsupported)
+        }
+
+        async fn group_schedule(&self, _client: &reqwest::Client, _term: &Term, _group_name: &str) -> Result<GroupSchedule, RozkladParseError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err((self.error)())
+        }
+    }
+
+    fn mock_sources(rozklad_url: &str) -> Vec<Box<dyn ScheduleSource>> {
+        vec![
+            Box::new(RozkladParser::new(rozklad_url.to_string())),
+            Box::new(RozkladApi::new(format!("{}/v2", rozklad_url))),
+        ]
+    }
+
+    async fn schedule_from_mock(term: &Term, name: &str) -> Result<GroupSchedule, RozkladParseError> {
+        let sources = ScheduleSources::new(mock_sources(&start_rozklad_mock()), 5, Duration::from_secs(60));
+        sources.group_schedule(&reqwest::Client::new(), term, name).await
+    }
+
+    fn failing_then_mock(error: fn() -> RozkladParseError, calls: &Arc<AtomicUsize>) -> ScheduleSources {
+        let mut sources: Vec<Box<dyn ScheduleSource>> = vec![Box::new(FailingSource { error, calls: calls.clone() })];
+        sources.append(&mut mock_sources(&start_rozklad_mock()));
+
+        ScheduleSources::new(sources, 2, Duration::from_secs(60))
+    }
+
+    #[actix_rt::test]
+    async fn first_term_schedule_is_parsed() {
+        let schedule = schedule_from_mock(&Term::First, "ІП-82").await.unwrap();
+
+        assert!(matches!(schedule.source, Some(GroupScheduleSource::Parser)));
+        assert_eq!(schedule.entries.len(), 7);
+        assert_eq!(schedule.entries[0].names, vec!["Бази даних"]);
+    }
+
+    #[actix_rt::test]
+    async fn second_term_schedule_is_parsed() {
+        let schedule = schedule_from_mock(&Term::Second, "ІП-82").await.unwrap();
+
+        assert!(matches!(schedule.source, Some(GroupScheduleSource::Parser)));
+        assert_eq!(schedule.entries.len(), 3);
+        assert_eq!(schedule.entries[0].names, vec!["Комп’ютерні мережі"]);
+    }
+
+    #[actix_rt::test]
+    async fn api_is_used_for_groups_unknown_to_rozklad() {
+        let schedule = schedule_from_mock(&Term::First, "ІП-83").await.unwrap();
+
+        assert!(matches!(schedule.source, Some(GroupScheduleSource::API)));
+        assert_eq!(schedule.entries[0].names, vec!["Англійська мова"]);
+    }
+
+    #[actix_rt::test]
+    async fn api_is_used_when_schedule_page_fails() {
+        let schedule = schedule_from_mock(&Term::First, "ІП-81").await.unwrap();
+
+        assert!(matches!(schedule.source, Some(GroupScheduleSource::API)));
+        assert_eq!(schedule.entries[0].names, vec!["Бази даних"]);
+    }
+
+    #[actix_rt::test]
+    async fn groups_are_listed_by_first_source_supporting_it() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let sources = failing_then_mock(|| RozkladParseError::RozkladErrored, &calls);
+
+        let groups = sources.groups(&reqwest::Client::new()).await;
+        assert!(groups.contains(&"ІП-82".to_string()));
+        assert!(!sources.sources[0].circuit.is_open());
+    }
+
+    #[actix_rt::test]
+    async fn listing_groups_from_unsupported_source_closes_circuit() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut sources: Vec<Box<dyn ScheduleSource>> = vec![Box::new(FailingSource { error: || RozkladParseError::RozkladErrored, calls: calls.clone() })];
+        sources.append(&mut mock_sources(&start_rozklad_mock()));
+        let sources = ScheduleSources::new(sources, 1, Duration::from_millis(50));
+        let client = reqwest::Client::new();
+
+        sources.group_schedule(&client, &Term::First, "ІП-82").await.unwrap();
+        assert!(sources.sources[0].circuit.is_open());
+
+        // groups request is the trial one, the source does not list groups
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(!sources.groups(&client).await.is_empty());
+        assert!(!sources.sources[0].circuit.is_open());
+
+        sources.group_schedule(&client, &Term::First, "ІП-82").await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn failing_source_is_skipped_after_threshold() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let sources = failing_then_mock(|| RozkladParseError::RozkladErrored, &calls);
+        let client = reqwest::Client::new();
+
+        for _ in 0..3 {
+            let schedule = sources.group_schedule(&client, &Term::First, "ІП-82").await.unwrap();
+            assert!(matches!(schedule.source, Some(GroupScheduleSource::Parser)));
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(sources.sources[0].circuit.is_open());
+    }
+
+    #[actix_rt::test]
+    async fn unknown_group_does_not_open_circuit() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let sources = failing_then_mock(|| RozkladParseError::GroupNotFound, &calls);
+        let client = reqwest::Client::new();
+
+        for _ in 0..3 {
+            sources.group_schedule(&client, &Term::First, "ІП-82").await.unwrap();
         }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(!sources.sources[0].circuit.is_open());
+    }
+
+    #[actix_rt::test]
+    async fn no_sources_available() {
+        let sources = ScheduleSources::new(vec![], 1, Duration::from_secs(60));
+
+        assert!(matches!(
+            sources.group_schedule(&reqwest::Client::new(), &Term::First, "ІП-82").await,
+            Err(RozkladParseError::NoSourceAvailable)
+        ));
     }
 }
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 use serde::Deserialize;
+use async_trait::async_trait;
 use crate::errors::RozkladParseError;
 use crate::models::schedule::*;
+use crate::models::academic_calendar::Term;
+use crate::schedule_source::ScheduleSource;
 
 #[derive(Deserialize, Debug)]
 struct GroupInfoResult {
@@ -40,6 +43,8 @@ struct GroupTimetableLesson {
     day_number: String,
     lesson_name: String,
     lesson_number: String,
+    #[serde(default)]
+    lesson_type: String,
     rooms: Vec<LessonRoom>,
     teachers: Vec<LessonTeacher>
 }
@@ -54,17 +59,56 @@ struct LessonTeacher {
     teacher_short_name: String,
 }
 
-// get schedule by group id
-pub async fn group_schedule(client: &reqwest::Client, name: &str) -> Result<GroupSchedule, RozkladParseError> {
-    let res = client.get(&format!("https://api.rozklad.org.ua/v2/groups/{}/timetable", name))
+// api.rozklad.org.ua, base_url is its address (see config::rozklad_api_url)
+pub struct RozkladApi {
+    base_url: String,
+}
+
+impl RozkladApi {
+
+    pub fn new(base_url: String) -> Self {
+        Self {
+            base_url,
+        }
+    }
+}
+
+#[async_trait]
+impl ScheduleSource for RozkladApi {
+
+    fn name(&self) -> &'static str {
+        "api"
+    }
+
+    async fn groups(&self, _client: &reqwest::Client) -> Result<Vec<String>, RozkladParseError> {
+        Err(RozkladParseError::SourceUnsupported)
+    }
+
+    // api only has schedule for the current term
+    async fn group_schedule(&self, client: &reqwest::Client, _term: &Term, group_name: &str) -> Result<GroupSchedule, RozkladParseError> {
+        group_schedule(client, &self.base_url, group_name).await
+    }
+}
+
+// get schedule by group name
+pub async fn group_schedule(client: &reqwest::Client, base_url: &str, name: &str) -> Result<GroupSchedule, RozkladParseError> {
+    let res = client.get(format!("{}/groups/{}/timetable", base_url, name))
         .send()
         .await?;
 
+    if res.status() == 404 {
+        return Err(RozkladParseError::GroupNotFound);
+    }
+
     if res.status() != 200 {
         return Err(RozkladParseError::RozkladApiErrored);
     }
 
-    let res: GroupTimetableResult = res.json().await?;
+    group_schedule_from_json(&res.text().await?)
+}
+
+fn group_schedule_from_json(json: &str) -> Result<GroupSchedule, RozkladParseError> {
+    let res: GroupTimetableResult = serde_json::from_str(json)?;
 
     let mut entries = vec![];
 
@@ -80,12 +124,17 @@ pub async fn group_schedule(client: &reqwest::Client, name: &str) -> Result<Grou
                         .with_names(vec![lesson.lesson_name])
                         .with_lecturers(lesson.teachers.iter().map(|v| v.teacher_short_name.clone()).collect())
                         .with_locations(lesson.rooms.iter().map(|v| v.room_name.clone()).collect())
+                        .with_lesson_type(LectureType::from_location(&lesson.lesson_type))
+                        .with_lesson_type_from_locations()
                 );
             }
         }
     }
 
-    Ok(GroupSchedule { entries, source: Some(GroupScheduleSource::API) })
+    // weeks and days are json objects, order entries the same way parser does
+    entries.sort_by_key(|v| (v.week.to_index(), v.index, v.day.to_index()));
+
+    Ok(GroupSchedule { entries, source: Some(GroupScheduleSource::API), updated_at: None, stale: false, cancelled_entries: Vec::new(), exceptions: Vec::new() })
 }
 
 // rozklad api test
@@ -93,9 +142,34 @@ pub async fn group_schedule(client: &reqwest::Client, name: &str) -> Result<Grou
 mod tests {
     use super::*;
     use more_asserts::assert_gt;
+    use crate::test_fixtures::{assert_matches_golden, fixture};
+
+    #[test]
+    fn group_timetable() {
+        let schedule = group_schedule_from_json(&fixture("rozklad_api/group_timetable.json")).unwrap();
+        assert_matches_golden(&schedule.entries, "rozklad_api/group_timetable");
+    }
+
+    #[test]
+    fn group_timetable_without_lesson_types() {
+        let schedule = group_schedule_from_json(&fixture("rozklad_api/group_timetable_without_lesson_types.json")).unwrap();
+
+        assert_matches_golden(&schedule.entries, "rozklad_api/group_timetable_without_lesson_types");
+        assert_eq!(schedule.entries[0].lesson_type, Some(LectureType::Practice));
+    }
 
+    #[test]
+    fn unexpected_response() {
+        assert!(matches!(
+            group_schedule_from_json(r#"{"statusCode":404,"message":"Group not found"}"#),
+            Err(RozkladParseError::ResponseParseFailed { .. })
+        ));
+    }
+
+    // uses live api.rozklad.org.ua, run with --ignored
     #[tokio::test]
+    #[ignore]
     async fn rozklad_group_schedule_ip82() {
-        assert_gt!(group_schedule(&reqwest::Client::new(), "ІП-82").await.unwrap().entries.len(), 0);
+        assert_gt!(group_schedule(&reqwest::Client::new(), "https://api.rozklad.org.ua/v2", "ІП-82").await.unwrap().entries.len(), 0);
     }
-}
\ No newline at end of file
+}
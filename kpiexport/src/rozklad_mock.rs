@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+use actix_web::http::header;
+use serde::Deserialize;
+use crate::test_fixtures::fixture;
+
+pub const IP82_GROUP_ID: &str = "494e5743-35fb-4a3f-b868-44662e6cd66e";
+const IP81_GROUP_ID: &str = "6d2a2a4d-5e1c-4a41-9f7b-0e6e4d6f0c2b"; // has no schedule page
+
+pub const CAMPUS_IP82_GROUP_ID: &str = "8f1c2a3b-5d6e-4f70-8a9b-0c1d2e3f4a5b"; // as in campus_api/groups.json
+
+const GROUPS: &[&str] = &["ІО-81", "ІП-81", "ІП-82", "КВ-91"];
+
+const SELECTION_VIEWSTATE: &str = "/wEPDwUKMTY0ODk5NjQwOQ9kFgJmD2QWAgIDD2QWAgIBD2QWAgIBD2QWAgIBDxYCHgVWYWx1ZWRkZA==";
+const SELECTION_EVENTVALIDATION: &str = "/wEdAAPg1Rqa5e3Ff9ASVp5F2fGJTsQTLF1jg8ifc4P4gU5P7d2bO2i3lQXL2NRs1ESn";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetGroupsRequest {
+    prefix_text: String,
+    count: usize,
+}
+
+#[derive(Deserialize)]
+struct ViewScheduleQuery {
+    g: String,
+}
+
+fn html(body: String) -> HttpResponse {
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body)
+}
+
+// what ASP.NET returns when the posted form state does not belong to the page
+fn invalid_viewstate() -> HttpResponse {
+    HttpResponse::InternalServerError().body("Validation of viewstate MAC failed.")
+}
+
+fn group_id(group_name: &str) -> Option<&'static str> {
+    match group_name {
+        "ІП-82" => Some(IP82_GROUP_ID),
+        "ІП-81" => Some(IP81_GROUP_ID),
+        _ => None,
+    }
+}
+
+fn group_selection_page() -> String {
+    format!(r#"<!DOCTYPE html>
+<html>
+<body>
+    <form method="post" action="./ScheduleGroupSelection.aspx" id="aspnetForm">
+<input type="hidden" name="__VIEWSTATE" id="__VIEWSTATE" value="{}" />
+<input type="hidden" name="__EVENTVALIDATION" id="__EVENTVALIDATION" value="{}" />
+        <input name="ctl00$MainContent$ctl00$txtboxGroup" type="text" id="ctl00_MainContent_ctl00_txtboxGroup" />
+        <input type="submit" name="ctl00$MainContent$ctl00$btnShowSchedule" value="Розклад занять" />
+    </form>
+</body>
+</html>"#, SELECTION_VIEWSTATE, SELECTION_EVENTVALIDATION)
+}
+
+#[get("/Schedules/ScheduleGroupSelection.aspx")]
+async fn group_selection() -> impl Responder {
+    html(group_selection_page())
+}
+
+#[post("/Schedules/ScheduleGroupSelection.aspx")]
+async fn select_group(form: web::Form<HashMap<String, String>>) -> impl Responder {
+    if form.get("__VIEWSTATE").map(String::as_str) != Some(SELECTION_VIEWSTATE)
+        || form.get("__EVENTVALIDATION").map(String::as_str) != Some(SELECTION_EVENTVALIDATION) {
+        return invalid_viewstate();
+    }
+
+    let group_name = form.get("ctl00$MainContent$ctl00$txtboxGroup").map(String::as_str).unwrap_or("");
+    match group_id(group_name) {
+        Some(id) => HttpResponse::Found()
+            .insert_header((header::LOCATION, format!("/Schedules/ViewSchedule.aspx?g={}", id)))
+            .finish(),
+        // rozklad shows the same form again when there is no such group
+        None => html(group_selection_page()),
+    }
+}
+
+#[post("/Schedules/ScheduleGroupSelection.aspx/GetGroups")]
+async fn groups_with_prefix(request: web::Json<GetGroupsRequest>) -> impl Responder {
+    let groups: Vec<&str> = GROUPS.iter()
+        .filter(|v| v.starts_with(&request.prefix_text))
+        .take(request.count)
+        .copied()
+        .collect();
+
+    HttpResponse::Ok().json(serde_json::json!({ "d": groups }))
+}
+
+#[get("/Schedules/ViewSchedule.aspx")]
+async fn first_term_schedule(query: web::Query<ViewScheduleQuery>) -> impl Responder {
+    if query.g != IP82_GROUP_ID {
+        return HttpResponse::NotFound().finish();
+    }
+
+    html(fixture("rozklad_parser/first_term.html"))
+}
+
+// term is switched with a postback of the first term page
+#[post("/Schedules/ViewSchedule.aspx")]
+async fn second_term_schedule(query: web::Query<ViewScheduleQuery>, form: web::Form<HashMap<String, String>>) -> impl Responder {
+    if query.g != IP82_GROUP_ID {
+        return HttpResponse::NotFound().finish();
+    }
+
+    let first_term_page = fixture("rozklad_parser/first_term.html");
+    let is_from_first_term_page = |name: &str| form.get(name)
+        .is_some_and(|v| first_term_page.contains(&format!("id=\"{}\" value=\"{}\"", name, v)));
+
+    if !is_from_first_term_page("__VIEWSTATE") || !is_from_first_term_page("__EVENTVALIDATION") {
+        return invalid_viewstate();
+    }
+
+    match form.get("ctl00$MainContent$ddlSemesterType").map(String::as_str) {
+        Some("2") => html(fixture("rozklad_parser/second_term.html")),
+        _ => html(first_term_page),
+    }
+}
+
+// ІП-81 has no schedule page on rozklad, but is in the api. ІП-83 is only known to the api.
+#[get("/v2/groups/{group_name}/timetable")]
+async fn api_timetable(group_name: web::Path<String>) -> impl Responder {
+    let response = match group_name.as_str() {
+        "ІП-81" | "ІП-82" => fixture("rozklad_api/group_timetable.json"),
+        "ІП-83" => fixture("rozklad_api/group_timetable_without_lesson_types.json"),
+        _ => return HttpResponse::NotFound().json(serde_json::json!({ "statusCode": 404, "message": "Group not found" })),
+    };
+
+    HttpResponse::Ok().content_type("application/json").body(response)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CampusLessonsQuery {
+    group_id: String,
+}
+
+#[get("/campus/schedule/groups")]
+async fn campus_groups() -> impl Responder {
+    HttpResponse::Ok().content_type("application/json").body(fixture("campus_api/groups.json"))
+}
+
+// only ІП-82 has lessons
+#[get("/campus/schedule/lessons")]
+async fn campus_lessons(query: web::Query<CampusLessonsQuery>) -> impl Responder {
+    if query.group_id != CAMPUS_IP82_GROUP_ID {
+        return HttpResponse::NotFound().finish();
+    }
+
+    HttpResponse::Ok().content_type("application/json").body(fixture("campus_api/lessons.json"))
+}
+
+// local stand-in for rozklad.kpi.ua and api.rozklad.org.ua which serves tests/fixtures. Forms are checked the
+// way rozklad does it: posts without VIEWSTATE/EVENTVALIDATION of the page they came from fail. Returns base url,
+// api is served under /v2 of it and campus api under /campus.
+pub fn start_rozklad_mock() -> String {
+    let server = HttpServer::new(|| App::new()
+            .service(group_selection)
+            .service(select_group)
+            .service(groups_with_prefix)
+            .service(first_term_schedule)
+            .service(second_term_schedule)
+            .service(api_timetable)
+            .service(campus_groups)
+            .service(campus_lessons)
+        )
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let address = server.addrs()[0];
+    actix_rt::spawn(server.run());
+    format!("http://{}", address)
+}
@@ -1,11 +1,13 @@
 use std::{collections::HashMap};
-use chrono::{Datelike, Utc};
 use scraper::{Html, Selector, ElementRef};
 use serde::Deserialize;
 use async_recursion::async_recursion;
+use async_trait::async_trait;
 use crate::models::schedule::*;
+use crate::models::academic_calendar::Term;
 use crate::errors::RozkladParseError;
 use crate::utils::group_id_from_url;
+use crate::schedule_source::ScheduleSource;
 
 const USER_AGENT: &'static str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/81.0.4044.92 Safari/537.36";
 const GROUP_PREFIXES: &'static [&'static str] = &[
@@ -13,12 +15,6 @@ const GROUP_PREFIXES: &'static [&'static str] = &[
     "С", "Т", "У", "Ф", "Х", "Ц", "Ч", "Ш", "Щ", "Ю", "Я"
 ];
 
-#[derive(PartialEq, Eq, Debug)]
-pub enum Term {
-    First,
-    Second
-}
-
 #[derive(Debug)]
 struct GroupSelectionPageFormData {
     // naming kept same to original form
@@ -38,24 +34,51 @@ struct GetGroupsResponse {
     d: Vec<String>
 }
 
-impl Term {
+// rozklad.kpi.ua pages, base_url is its address (see config::rozklad_url)
+pub struct RozkladParser {
+    base_url: String,
+}
+
+impl RozkladParser {
+
+    pub fn new(base_url: String) -> Self {
+        Self {
+            base_url,
+        }
+    }
+}
+
+#[async_trait]
+impl ScheduleSource for RozkladParser {
+
+    fn name(&self) -> &'static str {
+        "parser"
+    }
 
-    pub fn current() -> Self {
-        let current_month = Utc::now().month0();
-        if current_month == 0 || current_month >= 6 {
-            Self::First
-        } else {
-            Self::Second
+    async fn groups(&self, client: &reqwest::Client) -> Result<Vec<String>, RozkladParseError> {
+        // errors for every prefix are logged by get_groups
+        let groups = get_groups(client, &self.base_url).await;
+        if groups.is_empty() {
+            return Err(RozkladParseError::RozkladErrored);
         }
+
+        Ok(groups)
+    }
+
+    async fn group_schedule(&self, client: &reqwest::Client, term: &Term, group_name: &str) -> Result<GroupSchedule, RozkladParseError> {
+        let id = group_id_by_name(client, &self.base_url, group_name).await?;
+        info!("group id is: {}", id);
+
+        group_schedule(client, &self.base_url, term, &id).await.map(|v| v.0)
     }
 }
 
-// get all groups
-pub async fn get_groups(client: &reqwest::Client) -> Vec<String> {
+// get all groups, base_url is rozklad.kpi.ua address (see config::rozklad_url)
+pub async fn get_groups(client: &reqwest::Client, base_url: &str) -> Vec<String> {
     let mut groups = vec![];
 
     for prefix in GROUP_PREFIXES {
-        match get_groups_with_prefix(&client, prefix).await {
+        match get_groups_with_prefix(&client, base_url, prefix).await {
             Ok(v) => groups.append(&mut v.clone()),
             Err(err) => {
                 warn!("failed to get groups for prefix: {}", err);
@@ -67,8 +90,8 @@ pub async fn get_groups(client: &reqwest::Client) -> Vec<String> {
     groups
 }
 
-async fn get_groups_with_prefix(client: &reqwest::Client, prefix: &str) -> Result<Vec<String>, RozkladParseError> {
-    let res = client.post("http://rozklad.kpi.ua/Schedules/ScheduleGroupSelection.aspx/GetGroups")
+async fn get_groups_with_prefix(client: &reqwest::Client, base_url: &str, prefix: &str) -> Result<Vec<String>, RozkladParseError> {
+    let res = client.post(format!("{}/Schedules/ScheduleGroupSelection.aspx/GetGroups", base_url))
         .header("User-Agent", USER_AGENT)
         .header("Content-Type", "application/json; charset=UTF-8")
         .body(format!(r#"{{"prefixText":"{}","count":1000}}"#, prefix))
@@ -87,18 +110,18 @@ async fn get_groups_with_prefix(client: &reqwest::Client, prefix: &str) -> Resul
 
 // get schedule by group id
 #[async_recursion]
-pub async fn group_schedule(client: &reqwest::Client, term: &Term, id: &str) -> Result<(GroupSchedule, GroupSchedulePageFormData), RozkladParseError> {
+pub async fn group_schedule(client: &reqwest::Client, base_url: &str, term: &Term, id: &str) -> Result<(GroupSchedule, GroupSchedulePageFormData), RozkladParseError> {
     if term == &Term::First {
-        let res = client.get(&format!("http://rozklad.kpi.ua/Schedules/ViewSchedule.aspx?g={}", id))
+        let res = client.get(format!("{}/Schedules/ViewSchedule.aspx?g={}", base_url, id))
             .header("User-Agent", USER_AGENT)
             .send()
             .await?;
 
         group_schedule_from_html(&res.text().await?)
     } else if term == &Term::Second {
-        let first_term_reply = group_schedule(&client, &Term::First, &id).await?;
+        let first_term_reply = group_schedule(&client, base_url, &Term::First, &id).await?;
 
-        let res = client.post(&format!("http://rozklad.kpi.ua/Schedules/ViewSchedule.aspx?g={}", id))
+        let res = client.post(format!("{}/Schedules/ViewSchedule.aspx?g={}", base_url, id))
             .header("User-Agent", USER_AGENT)
             .form(&make_params_for_second_term_fetch(&first_term_reply.1))
             .send()
@@ -125,7 +148,7 @@ fn group_schedule_from_html(html: &str) -> Result<(GroupSchedule, GroupScheduleP
     let eventvalidation = get_input_value(&make_selector_and_select(&document, "#__EVENTVALIDATION")?)?;
 
     Ok((
-        GroupSchedule { entries, source: Some(GroupScheduleSource::Parser) },
+        GroupSchedule { entries, source: Some(GroupScheduleSource::Parser), updated_at: None, stale: false, cancelled_entries: Vec::new(), exceptions: Vec::new() },
         GroupSchedulePageFormData {
             viewstate,
             eventvalidation
@@ -219,6 +242,7 @@ fn parse_week(week_table: &ElementRef, week: ScheduleWeek) -> Vec<GroupScheduleE
                     .with_names(names)
                     .with_lecturers(lecturers)
                     .with_locations(locations)
+                    .with_lesson_type_from_locations()
                 );
             }
 
@@ -230,10 +254,10 @@ fn parse_week(week_table: &ElementRef, week: ScheduleWeek) -> Vec<GroupScheduleE
 }
 
 // get group id by name
-pub async fn group_id_by_name(client: &reqwest::Client, name: &str) -> Result<String, RozkladParseError> {
-    let group_selection_form_data = group_selection_page_form_data(&client).await?;
+pub async fn group_id_by_name(client: &reqwest::Client, base_url: &str, name: &str) -> Result<String, RozkladParseError> {
+    let group_selection_form_data = group_selection_page_form_data(&client, base_url).await?;
 
-    let res = client.post("http://rozklad.kpi.ua/Schedules/ScheduleGroupSelection.aspx")
+    let res = client.post(format!("{}/Schedules/ScheduleGroupSelection.aspx", base_url))
         .header("User-Agent", USER_AGENT)
         .form(&make_params(&group_selection_form_data, name))
         .send()
@@ -242,8 +266,8 @@ pub async fn group_id_by_name(client: &reqwest::Client, name: &str) -> Result<St
     group_id_from_url(&res.url().to_string())
 }
 
-async fn group_selection_page_form_data(client: &reqwest::Client) -> Result<GroupSelectionPageFormData, RozkladParseError> {
-    let res = client.get("http://rozklad.kpi.ua/Schedules/ScheduleGroupSelection.aspx")
+async fn group_selection_page_form_data(client: &reqwest::Client, base_url: &str) -> Result<GroupSelectionPageFormData, RozkladParseError> {
+    let res = client.get(format!("{}/Schedules/ScheduleGroupSelection.aspx", base_url))
         .header("User-Agent", USER_AGENT)
         .send()
         .await?;
@@ -324,29 +348,124 @@ fn get_attribute_value(element: &ElementRef, attr_name: &str) -> Result<String,
 mod tests {
     use super::*;
     use more_asserts::assert_gt;
+    use crate::rozklad_mock::{start_rozklad_mock, IP82_GROUP_ID};
+    use crate::test_fixtures::{assert_matches_golden, fixture};
+
+    fn entry_at(entries: &[GroupScheduleEntry], week: ScheduleWeek, day: ScheduleDay, index: u8) -> &GroupScheduleEntry {
+        entries.iter().find(|v| v.week == week && v.day == day && v.index == index).unwrap()
+    }
+
+    #[test]
+    fn first_term_page() {
+        let (schedule, form_data) = group_schedule_from_html(&fixture("rozklad_parser/first_term.html")).unwrap();
+
+        assert_matches_golden(&schedule.entries, "rozklad_parser/first_term");
+        assert!(form_data.viewstate.starts_with("/wEPDwULLTE4NzU0NzQ5NTkPZBYC"));
+        assert!(form_data.eventvalidation.ends_with("TL7a"));
+    }
+
+    #[test]
+    fn second_term_page() {
+        let (schedule, form_data) = group_schedule_from_html(&fixture("rozklad_parser/second_term.html")).unwrap();
+
+        assert_matches_golden(&schedule.entries, "rozklad_parser/second_term");
+        assert!(form_data.eventvalidation.ends_with("TL7b"));
+    }
+
+    #[test]
+    fn page_without_schedule_tables() {
+        assert!(matches!(
+            group_schedule_from_html(&fixture("rozklad_parser/unknown_group.html")),
+            Err(RozkladParseError::HtmlParseFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn empty_days_are_skipped() {
+        let html = fixture("rozklad_parser/first_term.html");
+        let document = Html::parse_document(&html);
+        let week_table = make_selector_and_select(&document, "#ctl00_MainContent_SecondScheduleTable>tbody").unwrap();
+
+        let entries = parse_week(&week_table, ScheduleWeek::Second);
+        let slots: Vec<(ScheduleDay, u8)> = entries.iter().map(|v| (v.day.clone(), v.index)).collect();
+        assert_eq!(slots, vec![(ScheduleDay::Monday, 0), (ScheduleDay::Wednesday, 1)]);
+        assert!(entries.iter().all(|v| v.week == ScheduleWeek::Second));
+    }
+
+    #[test]
+    fn lesson_split_between_subgroups() {
+        let (schedule, _) = group_schedule_from_html(&fixture("rozklad_parser/first_term.html")).unwrap();
+        let entry = entry_at(&schedule.entries, ScheduleWeek::First, ScheduleDay::Tuesday, 1);
+
+        assert_eq!(entry.names, vec!["Англійська мова", "Німецька мова"]);
+        assert_eq!(entry.lecturers, vec!["вик. Коваленко О. М.", "вик. Шевчук А. В."]);
+        assert_eq!(entry.locations, vec!["7-301", "7-302"]);
+        assert_eq!(entry.lesson_type, Some(LectureType::Practice));
+    }
+
+    #[test]
+    fn lecturers_and_rooms_without_links_are_not_parsed() {
+        let (schedule, _) = group_schedule_from_html(&fixture("rozklad_parser/first_term.html")).unwrap();
+
+        let entry = entry_at(&schedule.entries, ScheduleWeek::First, ScheduleDay::Friday, 2);
+        assert_eq!(entry.names, vec!["Фізичне виховання"]);
+        assert!(entry.lecturers.is_empty());
+        assert!(entry.locations.is_empty());
+
+        let entry = entry_at(&schedule.entries, ScheduleWeek::Second, ScheduleDay::Wednesday, 1);
+        assert!(entry.lecturers.is_empty());
+        assert_eq!(entry.locations, vec!["18-1005"]);
+    }
+
+    #[actix_rt::test]
+    async fn group_id_is_taken_from_redirect() {
+        let rozklad_url = start_rozklad_mock();
+        let client = reqwest::Client::new();
+
+        assert_eq!(group_id_by_name(&client, &rozklad_url, "ІП-82").await.unwrap(), IP82_GROUP_ID);
+        assert!(matches!(
+            group_id_by_name(&client, &rozklad_url, "ІП-99").await,
+            Err(RozkladParseError::FailedToParseGroupId)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn groups_are_listed_by_prefix() {
+        let rozklad_url = start_rozklad_mock();
+
+        let groups = get_groups_with_prefix(&reqwest::Client::new(), &rozklad_url, "ІП").await.unwrap();
+        assert_eq!(groups, vec!["ІП-81", "ІП-82"]);
+    }
+
+    // tests below use live rozklad.kpi.ua, run them with --ignored
+    const LIVE_ROZKLAD_URL: &str = "http://rozklad.kpi.ua";
 
     #[tokio::test]
+    #[ignore]
     async fn rozklad_get_id_ip82() {
-        let ip82_group_id = group_id_by_name(&reqwest::Client::new(), "ІП-82").await.unwrap();
-        let ip81_group_id = group_id_by_name(&reqwest::Client::new(), "ІП-81").await.unwrap();
+        let ip82_group_id = group_id_by_name(&reqwest::Client::new(), LIVE_ROZKLAD_URL, "ІП-82").await.unwrap();
+        let ip81_group_id = group_id_by_name(&reqwest::Client::new(), LIVE_ROZKLAD_URL, "ІП-81").await.unwrap();
 
         assert_eq!(ip82_group_id.len(), 36);
         assert_ne!(ip82_group_id, ip81_group_id);
     }
 
     #[tokio::test]
+    #[ignore]
     async fn rozklad_schedule_ip82() {
-        let ip82_group_id = group_id_by_name(&reqwest::Client::new(), "ІП-82").await.unwrap();
-        assert_gt!(group_schedule(&reqwest::Client::new(), &Term::First, &ip82_group_id).await.unwrap().0.entries.len(), 0);
+        let ip82_group_id = group_id_by_name(&reqwest::Client::new(), LIVE_ROZKLAD_URL, "ІП-82").await.unwrap();
+        assert_gt!(group_schedule(&reqwest::Client::new(), LIVE_ROZKLAD_URL, &Term::First, &ip82_group_id).await.unwrap().0.entries.len(), 0);
     }
 
     #[tokio::test]
+    #[ignore]
     async fn rozklad_schedule_ip82_second_term() {
-        println!("result is {:?}", group_schedule(&reqwest::Client::new(), &Term::Second, "494e5743-35fb-4a3f-b868-44662e6cd66e").await.unwrap().0.entries);
+        println!("result is {:?}", group_schedule(&reqwest::Client::new(), LIVE_ROZKLAD_URL, &Term::Second, "494e5743-35fb-4a3f-b868-44662e6cd66e").await.unwrap().0.entries);
     }
 
     #[tokio::test]
+    #[ignore]
     async fn rozklad_groups() {
-        assert_gt!(get_groups_with_prefix(&reqwest::Client::new(), "І").await.unwrap().len(), 0);
+        assert_gt!(get_groups_with_prefix(&reqwest::Client::new(), LIVE_ROZKLAD_URL, "І").await.unwrap().len(), 0);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,171 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use lru::LruCache;
+use crate::models::academic_calendar::Term;
+use crate::models::schedule::GroupSchedule;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ScheduleCacheKey {
+    group_name: String,
+    academic_year: i32,
+    term: Term,
+}
+
+struct CachedSchedule {
+    schedule: GroupSchedule,
+    cached_at: Instant,
+}
+
+// group schedules ready to be returned to clients, with subject ids and lesson times already set
+pub struct ScheduleCache {
+    entries: Mutex<LruCache<ScheduleCacheKey, CachedSchedule>>,
+    capacity: usize,
+    ttl: Duration,
+    generation: AtomicU64, // changed on every invalidation
+}
+
+impl ScheduleCache {
+
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+            capacity,
+            ttl,
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, group_name: &str, academic_year: i32, term: Term) -> Option<GroupSchedule> {
+        let key = ScheduleCacheKey {
+            group_name: group_name.to_string(),
+            academic_year,
+            term,
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(&key) {
+            Some(cached) if cached.cached_at.elapsed() < self.ttl => Some(cached.schedule.clone()),
+            Some(_) => {
+                entries.pop(&key);
+                None
+            },
+            None => None,
+        }
+    }
+
+    // should be taken before loading the schedule to insert, see insert
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    // schedule is not cached if anything was invalidated since the generation was taken, it could have been
+    // loaded before the update
+    pub fn insert(&self, generation: u64, group_name: &str, academic_year: i32, term: Term, schedule: GroupSchedule) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let key = ScheduleCacheKey {
+            group_name: group_name.to_string(),
+            academic_year,
+            term,
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        if self.generation() != generation {
+            return;
+        }
+
+        entries.put(key, CachedSchedule {
+            schedule,
+            cached_at: Instant::now(),
+        });
+    }
+
+    // drops the group schedule for every term
+    pub fn invalidate_group(&self, group_name: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        let keys: Vec<ScheduleCacheKey> = entries.iter()
+            .map(|(key, _)| key)
+            .filter(|key| key.group_name == group_name)
+            .cloned()
+            .collect();
+
+        for key in keys {
+            entries.pop(&key);
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> GroupSchedule {
+        GroupSchedule {
+            entries: vec![],
+            source: None,
+            updated_at: None,
+            stale: false,
+            cancelled_entries: Vec::new(),
+            exceptions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn expired_schedules_are_not_returned() {
+        let cache = ScheduleCache::new(10, Duration::from_secs(60));
+        cache.insert(cache.generation(), "ІП-82", 2021, Term::First, schedule());
+        assert!(cache.get("ІП-82", 2021, Term::First).is_some());
+        assert!(cache.get("ІП-82", 2021, Term::Second).is_none());
+
+        let cache = ScheduleCache::new(10, Duration::from_secs(0));
+        cache.insert(cache.generation(), "ІП-82", 2021, Term::First, schedule());
+        assert!(cache.get("ІП-82", 2021, Term::First).is_none());
+    }
+
+    #[test]
+    fn updated_group_is_invalidated() {
+        let cache = ScheduleCache::new(10, Duration::from_secs(60));
+        cache.insert(cache.generation(), "ІП-82", 2021, Term::First, schedule());
+        cache.insert(cache.generation(), "ІП-82", 2021, Term::Second, schedule());
+        cache.insert(cache.generation(), "ІП-81", 2021, Term::First, schedule());
+
+        cache.invalidate_group("ІП-82");
+
+        assert!(cache.get("ІП-82", 2021, Term::First).is_none());
+        assert!(cache.get("ІП-82", 2021, Term::Second).is_none());
+        assert!(cache.get("ІП-81", 2021, Term::First).is_some());
+    }
+
+    #[test]
+    fn schedule_loaded_before_update_is_not_cached() {
+        let cache = ScheduleCache::new(10, Duration::from_secs(60));
+        let generation = cache.generation();
+        cache.invalidate_group("ІП-82");
+
+        cache.insert(generation, "ІП-82", 2021, Term::First, schedule());
+        assert!(cache.get("ІП-82", 2021, Term::First).is_none());
+    }
+
+    #[test]
+    fn least_recently_used_schedule_is_evicted() {
+        let cache = ScheduleCache::new(2, Duration::from_secs(60));
+        cache.insert(cache.generation(), "ІП-81", 2021, Term::First, schedule());
+        cache.insert(cache.generation(), "ІП-82", 2021, Term::First, schedule());
+        cache.get("ІП-81", 2021, Term::First);
+        cache.insert(cache.generation(), "ІП-83", 2021, Term::First, schedule());
+
+        assert!(cache.get("ІП-81", 2021, Term::First).is_some());
+        assert!(cache.get("ІП-82", 2021, Term::First).is_none());
+    }
+}
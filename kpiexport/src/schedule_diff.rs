@@ -0,0 +1,107 @@
+use crate::models::schedule::GroupScheduleEntry;
+use crate::models::schedule_changes::{ScheduleChange, ScheduleChangeType};
+
+// lessons are matched by names: first in the same time slot, then in any other slot (moved lesson).
+// Whatever is left was removed or added.
+pub fn diff_schedules(old_entries: &[GroupScheduleEntry], new_entries: &[GroupScheduleEntry]) -> Vec<ScheduleChange> {
+    let mut changes = Vec::new();
+    let mut not_found: Vec<&GroupScheduleEntry> = Vec::new();
+    let mut not_matched: Vec<&GroupScheduleEntry> = new_entries.iter().collect();
+
+    for old_entry in old_entries {
+        let same_slot = not_matched.iter()
+            .position(|v| v.names == old_entry.names && is_same_slot(old_entry, v));
+
+        match same_slot {
+            Some(position) => {
+                let new_entry = not_matched.remove(position);
+                if !has_same_details(old_entry, new_entry) {
+                    changes.push(change(ScheduleChangeType::Changed, Some(old_entry), Some(new_entry)));
+                }
+            },
+            None => not_found.push(old_entry),
+        }
+    }
+
+    for old_entry in not_found {
+        match not_matched.iter().position(|v| v.names == old_entry.names) {
+            Some(position) => {
+                let new_entry = not_matched.remove(position);
+                changes.push(change(ScheduleChangeType::Moved, Some(old_entry), Some(new_entry)));
+            },
+            None => changes.push(change(ScheduleChangeType::Removed, Some(old_entry), None)),
+        }
+    }
+
+    for new_entry in not_matched {
+        changes.push(change(ScheduleChangeType::Added, None, Some(new_entry)));
+    }
+
+    changes.sort_by_key(|v| {
+        let entry = v.after.as_ref().or(v.before.as_ref()).unwrap();
+        (entry.week.to_index(), entry.day.to_index(), entry.index)
+    });
+    changes
+}
+
+fn is_same_slot(a: &GroupScheduleEntry, b: &GroupScheduleEntry) -> bool {
+    a.week == b.week && a.day == b.day && a.index == b.index
+}
+
+fn has_same_details(a: &GroupScheduleEntry, b: &GroupScheduleEntry) -> bool {
+    a.lecturers == b.lecturers && a.locations == b.locations && a.lesson_type == b.lesson_type
+}
+
+fn change(change_type: ScheduleChangeType, before: Option<&GroupScheduleEntry>, after: Option<&GroupScheduleEntry>) -> ScheduleChange {
+    ScheduleChange {
+        change_type,
+        before: before.cloned(),
+        after: after.cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schedule::{ScheduleDay, ScheduleWeek};
+
+    fn lesson(day: ScheduleDay, index: u8, name: &str, location: &str) -> GroupScheduleEntry {
+        GroupScheduleEntry::new(ScheduleWeek::First, day, index)
+            .with_names(vec![name.into()])
+            .with_locations(vec![location.into()])
+    }
+
+    #[test]
+    fn unchanged_schedule_has_no_changes() {
+        let schedule = vec![lesson(ScheduleDay::Monday, 0, "Бази даних", "7-418")];
+        assert!(diff_schedules(&schedule, &schedule).is_empty());
+    }
+
+    #[test]
+    fn moved_and_changed_lessons_are_detected() {
+        let old_schedule = vec![
+            lesson(ScheduleDay::Monday, 0, "Бази даних", "7-418"),
+            lesson(ScheduleDay::Tuesday, 1, "Англійська мова", "7-301"),
+            lesson(ScheduleDay::Thursday, 2, "Бази даних", "7-203"),
+            lesson(ScheduleDay::Friday, 3, "Фізичне виховання", "спортзал"),
+        ];
+        let new_schedule = vec![
+            lesson(ScheduleDay::Monday, 0, "Бази даних", "7-418"),
+            lesson(ScheduleDay::Tuesday, 1, "Англійська мова", "7-302"),
+            lesson(ScheduleDay::Friday, 1, "Бази даних", "7-203"),
+            lesson(ScheduleDay::Saturday, 0, "Операційні системи", "18-1005"),
+        ];
+
+        let changes = diff_schedules(&old_schedule, &new_schedule);
+        let change_types: Vec<ScheduleChangeType> = changes.iter().map(|v| v.change_type).collect();
+        assert_eq!(change_types, vec![
+            ScheduleChangeType::Changed,
+            ScheduleChangeType::Moved,
+            ScheduleChangeType::Removed,
+            ScheduleChangeType::Added,
+        ]);
+
+        assert_eq!(changes[1].before.as_ref().unwrap().day, ScheduleDay::Thursday);
+        assert_eq!(changes[1].after.as_ref().unwrap().day, ScheduleDay::Friday);
+    }
+}
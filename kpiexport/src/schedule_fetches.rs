@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::models::schedule::GroupSchedule;
+use crate::rozklad::ScheduleSources;
+use crate::single_flight::SingleFlight;
+
+// fetches of group schedules from rozklad, shared by all requests of the web server
+pub struct GroupScheduleFetches {
+    in_flight: SingleFlight<Result<GroupSchedule, String>>,
+    stale_refreshes: Mutex<HashMap<String, Instant>>, // when background refresh of a stale schedule last started
+    stale_refresh_interval: Duration,
+    sources: ScheduleSources, // shared, so that circuit breakers see failures of all requests
+}
+
+impl GroupScheduleFetches {
+
+    pub fn new(stale_refresh_interval: Duration, sources: ScheduleSources) -> Self {
+        Self {
+            in_flight: SingleFlight::new(),
+            stale_refreshes: Mutex::new(HashMap::new()),
+            stale_refresh_interval,
+            sources,
+        }
+    }
+
+    pub fn sources(&self) -> &ScheduleSources {
+        &self.sources
+    }
+
+    // only one fetch per group at a time, second value is true if the result was fetched by another call
+    pub async fn run<F, Fut>(&self, group_name: &str, f: F) -> (Result<GroupSchedule, String>, bool)
+        where F: FnOnce() -> Fut, Fut: Future<Output = Result<GroupSchedule, String>> {
+        self.in_flight.run(group_name, f).await
+    }
+
+    // false if a refresh of the group started less than stale_refresh_interval ago. Every request gets a stale
+    // schedule while rozklad is down, they should not all try to fetch it again.
+    pub fn start_stale_refresh(&self, group_name: &str) -> bool {
+        let mut stale_refreshes = self.stale_refreshes.lock().unwrap();
+        stale_refreshes.retain(|_, started_at| started_at.elapsed() < self.stale_refresh_interval);
+
+        if stale_refreshes.contains_key(group_name) {
+            return false;
+        }
+
+        stale_refreshes.insert(group_name.to_string(), Instant::now());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetches(stale_refresh_interval: Duration) -> GroupScheduleFetches {
+        GroupScheduleFetches::new(stale_refresh_interval, ScheduleSources::new(vec![], 1, Duration::from_secs(60)))
+    }
+
+    #[test]
+    fn stale_refresh_is_throttled_per_group() {
+        let fetches = fetches(Duration::from_secs(60));
+
+        assert!(fetches.start_stale_refresh("ІП-82"));
+        assert!(!fetches.start_stale_refresh("ІП-82"));
+        assert!(fetches.start_stale_refresh("ІП-83"));
+    }
+
+    #[test]
+    fn stale_refresh_is_allowed_again_after_interval() {
+        let fetches = fetches(Duration::from_millis(0));
+
+        assert!(fetches.start_stale_refresh("ІП-82"));
+        assert!(fetches.start_stale_refresh("ІП-82"));
+    }
+}
@@ -0,0 +1,296 @@
+use chrono::NaiveDate;
+use crate::models::academic_calendar::AcademicCalendar;
+use crate::models::schedule::{GroupSchedule, GroupScheduleEntry, ScheduleException};
+use crate::models::schedule_overrides::{ScheduleOverride, ScheduleOverrideAction};
+
+// overrides are applied in the order they were added, so a later one can replace a lesson added by an earlier one.
+// Overrides without dates change the weekly entries. Ones limited to dates only change lessons on the dates of
+// from..=until when the time slot has a lesson, these are returned as exceptions. Cancel and replace of a time slot
+// without lessons do nothing.
+pub fn apply_schedule_overrides(
+    schedule: GroupSchedule,
+    overrides: &[ScheduleOverride],
+    calendar: &AcademicCalendar,
+    from: NaiveDate,
+    until: NaiveDate
+) -> GroupSchedule {
+    let mut entries = schedule.entries;
+    let mut cancelled_entries = schedule.cancelled_entries;
+
+    for schedule_override in overrides.iter().filter(|v| !v.is_limited_to_dates()) {
+        apply_override(&mut entries, &mut cancelled_entries, schedule_override);
+    }
+
+    let mut exceptions: Vec<ScheduleException> = schedule.exceptions;
+
+    for schedule_override in overrides.iter().filter(|v| v.is_limited_to_dates()) {
+        for date in schedule_override.lesson_dates(calendar, from, until) {
+            let position = exceptions.iter()
+                .position(|v| v.date == date && v.index == schedule_override.index);
+            let exception = match position {
+                Some(position) => &mut exceptions[position],
+                None => {
+                    exceptions.push(ScheduleException {
+                        date,
+                        index: schedule_override.index,
+                        entries: entries.iter().filter(|v| is_in_slot(v, schedule_override)).cloned().collect(),
+                        cancelled_entries: Vec::new(),
+                    });
+                    exceptions.last_mut().unwrap()
+                }
+            };
+
+            apply_override(&mut exception.entries, &mut exception.cancelled_entries, schedule_override);
+        }
+    }
+
+    entries.sort_by_key(|v| (v.week.to_index(), v.index, v.day.to_index()));
+    exceptions.sort_by_key(|v| (v.date, v.index));
+
+    GroupSchedule {
+        entries,
+        cancelled_entries,
+        exceptions,
+        ..schedule
+    }
+}
+
+fn apply_override(entries: &mut Vec<GroupScheduleEntry>, cancelled_entries: &mut Vec<GroupScheduleEntry>, schedule_override: &ScheduleOverride) {
+    match schedule_override.action {
+        ScheduleOverrideAction::Add => entries.push(
+            GroupScheduleEntry::new(schedule_override.week.clone(), schedule_override.day.clone(), schedule_override.index)
+                .with_names(schedule_override.names.clone())
+                .with_lecturers(schedule_override.lecturers.clone())
+                .with_locations(schedule_override.locations.clone())
+                .with_lesson_type(schedule_override.lesson_type)
+                .with_override(schedule_override.marker())
+        ),
+        ScheduleOverrideAction::Cancel => {
+            let (cancelled, rest): (Vec<_>, Vec<_>) = entries.drain(..).partition(|v| is_in_slot(v, schedule_override));
+            *entries = rest;
+            cancelled_entries.extend(cancelled.into_iter().map(|v| v.with_override(schedule_override.marker())));
+        },
+        ScheduleOverrideAction::Replace => {
+            for entry in entries.iter_mut().filter(|v| is_in_slot(v, schedule_override)) {
+                *entry = replaced(entry.clone(), schedule_override);
+            }
+        },
+    }
+}
+
+fn is_in_slot(entry: &GroupScheduleEntry, schedule_override: &ScheduleOverride) -> bool {
+    entry.week == schedule_override.week && entry.day == schedule_override.day && entry.index == schedule_override.index
+}
+
+// only what is set in the override, so a room change keeps lecturers and names
+fn replaced(entry: GroupScheduleEntry, schedule_override: &ScheduleOverride) -> GroupScheduleEntry {
+    let mut entry = entry;
+
+    if !schedule_override.names.is_empty() {
+        entry = entry.with_names(schedule_override.names.clone());
+    }
+    if !schedule_override.lecturers.is_empty() {
+        entry = entry.with_lecturers(schedule_override.lecturers.clone());
+    }
+    if !schedule_override.locations.is_empty() {
+        entry = entry.with_locations(schedule_override.locations.clone());
+    }
+    if schedule_override.lesson_type.is_some() {
+        entry = entry.with_lesson_type(schedule_override.lesson_type);
+    }
+
+    entry.with_override(schedule_override.marker())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Utc};
+    use crate::models::academic_calendar::{AcademicTerm, Term};
+    use crate::models::schedule::{LectureType, ScheduleDay, ScheduleWeek};
+
+    // 2021-10-04 is a monday of the second week, 2021-10-11 of the first one
+    fn calendar() -> AcademicCalendar {
+        AcademicCalendar::new(
+            vec![AcademicTerm {
+                academic_year: 2021,
+                term: Term::First,
+                starts_on: NaiveDate::from_ymd(2021, 9, 1),
+                ends_on: NaiveDate::from_ymd(2021, 12, 24),
+                first_week_starts_on: NaiveDate::from_ymd(2021, 8, 30),
+            }],
+            Vec::new()
+        )
+    }
+
+    fn apply(schedule: GroupSchedule, overrides: &[ScheduleOverride]) -> GroupSchedule {
+        apply_schedule_overrides(schedule, overrides, &calendar(), NaiveDate::from_ymd(2021, 10, 4), NaiveDate::from_ymd(2021, 10, 17))
+    }
+
+    fn schedule() -> GroupSchedule {
+        GroupSchedule {
+            entries: vec![
+                GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 0)
+                    .with_names(vec!["Бази даних".into()])
+                    .with_lecturers(vec!["доц. Іваненко І. І.".into()])
+                    .with_locations(vec!["7-418".into()])
+                    .with_lesson_type(Some(LectureType::Lecture)),
+                GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 1)
+                    .with_names(vec!["Фізика".into()]),
+            ],
+            source: None,
+            updated_at: None,
+            stale: false,
+            cancelled_entries: Vec::new(),
+            exceptions: Vec::new(),
+        }
+    }
+
+    fn schedule_override(id: i32, action: ScheduleOverrideAction, index: u8) -> ScheduleOverride {
+        ScheduleOverride {
+            id,
+            group_name: "ІП-82".into(),
+            action,
+            week: ScheduleWeek::First,
+            day: ScheduleDay::Monday,
+            index,
+            names: Vec::new(),
+            lecturers: Vec::new(),
+            locations: Vec::new(),
+            lesson_type: None,
+            valid_from: None,
+            valid_until: None,
+            comment: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn room_change_keeps_the_rest_of_the_lesson() {
+        let room_change = ScheduleOverride {
+            locations: vec!["7-302".into()],
+            comment: Some("7-418 is closed".into()),
+            ..schedule_override(1, ScheduleOverrideAction::Replace, 0)
+        };
+
+        let schedule = apply(schedule(), &[room_change]);
+        let entry = &schedule.entries[0];
+
+        assert_eq!(entry.locations, vec!["7-302"]);
+        assert_eq!(entry.lecturers, vec!["доц. Іваненко І. І."]);
+        assert_eq!(entry.lesson_type, Some(LectureType::Lecture));
+        assert_eq!(entry.overridden.as_ref().unwrap().id, 1);
+        assert!(schedule.entries[1].overridden.is_none());
+    }
+
+    #[test]
+    fn cancelled_lessons_are_moved_out_of_entries() {
+        let cancel = ScheduleOverride {
+            comment: Some("the course is over".into()),
+            ..schedule_override(2, ScheduleOverrideAction::Cancel, 1)
+        };
+
+        let schedule = apply(schedule(), &[cancel]);
+
+        assert_eq!(schedule.entries.len(), 1);
+        assert_eq!(schedule.cancelled_entries.len(), 1);
+        assert_eq!(schedule.cancelled_entries[0].names, vec!["Фізика"]);
+
+        let json = serde_json::to_value(&schedule).unwrap();
+        assert_eq!(json["cancelled_entries"][0]["overridden"], serde_json::json!({
+            "id": 2,
+            "action": "cancel",
+            "comment": "the course is over",
+        }));
+        assert!(json["entries"][0].get("overridden").is_none());
+    }
+
+    #[test]
+    fn overrides_with_dates_do_not_change_weekly_entries() {
+        let cancel = ScheduleOverride {
+            valid_from: Some(NaiveDate::from_ymd(2021, 10, 11)),
+            valid_until: Some(NaiveDate::from_ymd(2021, 10, 11)),
+            ..schedule_override(2, ScheduleOverrideAction::Cancel, 1)
+        };
+        let room_change = ScheduleOverride {
+            locations: vec!["7-302".into()],
+            valid_from: Some(NaiveDate::from_ymd(2021, 10, 11)),
+            valid_until: Some(NaiveDate::from_ymd(2021, 10, 11)),
+            ..schedule_override(3, ScheduleOverrideAction::Replace, 0)
+        };
+        let consultation = ScheduleOverride {
+            names: vec!["Консультація".into()],
+            valid_from: Some(NaiveDate::from_ymd(2021, 10, 11)),
+            valid_until: Some(NaiveDate::from_ymd(2021, 10, 11)),
+            ..schedule_override(4, ScheduleOverrideAction::Add, 1)
+        };
+
+        let schedule = apply(schedule(), &[cancel, room_change, consultation]);
+
+        assert_eq!(serde_json::to_value(&schedule.entries).unwrap(), serde_json::to_value(&self::schedule().entries).unwrap());
+        assert!(schedule.cancelled_entries.is_empty());
+
+        let exceptions: Vec<(NaiveDate, u8)> = schedule.exceptions.iter().map(|v| (v.date, v.index)).collect();
+        assert_eq!(exceptions, vec![(NaiveDate::from_ymd(2021, 10, 11), 0), (NaiveDate::from_ymd(2021, 10, 11), 1)]);
+
+        assert_eq!(schedule.exceptions[0].entries[0].locations, vec!["7-302"]);
+        assert_eq!(schedule.exceptions[0].entries[0].names, vec!["Бази даних"]);
+
+        assert_eq!(schedule.exceptions[1].entries.len(), 1);
+        assert_eq!(schedule.exceptions[1].entries[0].names, vec!["Консультація"]);
+        assert_eq!(schedule.exceptions[1].cancelled_entries[0].names, vec!["Фізика"]);
+    }
+
+    #[test]
+    fn added_lesson_can_be_replaced_by_later_override() {
+        let add = ScheduleOverride {
+            names: vec!["Консультація".into()],
+            ..schedule_override(3, ScheduleOverrideAction::Add, 2)
+        };
+        let replace = ScheduleOverride {
+            lesson_type: Some(LectureType::Practice),
+            ..schedule_override(4, ScheduleOverrideAction::Replace, 2)
+        };
+
+        let schedule = apply(schedule(), &[add, replace]);
+        let entry = &schedule.entries[2];
+
+        assert_eq!(entry.names, vec!["Консультація"]);
+        assert_eq!(entry.lesson_type, Some(LectureType::Practice));
+        assert_eq!(entry.overridden.as_ref().unwrap().id, 4);
+    }
+
+    #[test]
+    fn empty_slot_is_not_changed() {
+        let schedule = apply(schedule(), &[
+            schedule_override(5, ScheduleOverrideAction::Cancel, 4),
+            schedule_override(6, ScheduleOverrideAction::Replace, 4),
+        ]);
+
+        assert_eq!(schedule.entries.len(), 2);
+        assert!(schedule.cancelled_entries.is_empty());
+        assert!(schedule.entries.iter().all(|v| v.overridden.is_none()));
+    }
+
+    #[test]
+    fn override_is_applied_only_when_lesson_happens_in_its_dates() {
+        // a monday of the second week, the lesson is on mondays of the first one
+        let cancel = ScheduleOverride {
+            valid_from: Some(NaiveDate::from_ymd(2021, 10, 4)),
+            valid_until: Some(NaiveDate::from_ymd(2021, 10, 4)),
+            ..schedule_override(7, ScheduleOverrideAction::Cancel, 0)
+        };
+        // the lesson of 2021-10-11 is not shown any more
+        let add = ScheduleOverride {
+            names: vec!["Консультація".into()],
+            valid_from: Some(NaiveDate::from_ymd(2021, 10, 11)),
+            valid_until: Some(NaiveDate::from_ymd(2021, 10, 11)),
+            ..schedule_override(8, ScheduleOverrideAction::Add, 3)
+        };
+
+        let schedule = apply_schedule_overrides(schedule(), &[cancel, add], &calendar(), NaiveDate::from_ymd(2021, 10, 12), NaiveDate::from_ymd(2021, 10, 25));
+
+        assert_eq!(schedule.entries.len(), 2);
+        assert!(schedule.exceptions.is_empty());
+    }
+}
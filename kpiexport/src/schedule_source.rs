@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use crate::errors::RozkladParseError;
+use crate::models::academic_calendar::Term;
+use crate::models::schedule::GroupSchedule;
+
+// upstream which group schedules are fetched from. Sources are tried in order by rozklad::ScheduleSources,
+// a new one is added to rozklad::source_by_name.
+#[async_trait]
+pub trait ScheduleSource: Send + Sync {
+
+    // as it is set in SCHEDULE_SOURCES, also used in logs and metrics
+    fn name(&self) -> &'static str;
+
+    // RozkladParseError::SourceUnsupported if the source cannot list groups
+    async fn groups(&self, client: &reqwest::Client) -> Result<Vec<String>, RozkladParseError>;
+
+    // RozkladParseError::GroupNotFound (or FailedToParseGroupId) if the source does not know the group
+    async fn group_schedule(&self, client: &reqwest::Client, term: &Term, group_name: &str) -> Result<GroupSchedule, RozkladParseError>;
+}
@@ -0,0 +1,42 @@
+use tokio_postgres::GenericClient;
+use crate::errors::PersistenceError;
+use crate::models::schedule::GroupSchedule;
+use crate::models::schedule_changes::{save_schedule_changes, ScheduleChange};
+use crate::models::schedule_queries::{group_schedule_entries, notify_schedule_updated, replace_group_schedule};
+use crate::models::webhooks::enqueue_webhook_deliveries;
+use crate::schedule_diff::diff_schedules;
+use crate::webhooks::schedule_changed_payload;
+
+// saves a freshly fetched schedule in one transaction: replaces the stored one, records what changed, enqueues
+// webhooks about the changes and notifies web servers. Nothing is changed if any of it fails.
+pub async fn save_group_schedule<C: GenericClient>(database: &mut C, group_name: &str, schedule: &GroupSchedule) -> Result<Vec<ScheduleChange>, PersistenceError> {
+    let mut transaction = database.transaction().await?;
+
+    let old_entries = group_schedule_entries(&transaction, group_name).await?;
+    replace_group_schedule(&mut transaction, group_name, schedule).await?;
+
+    // first schedule of the group is not a change
+    let changes = if old_entries.is_empty() {
+        Vec::new()
+    } else {
+        diff_schedules(&old_entries, &schedule.entries)
+    };
+
+    if !changes.is_empty() {
+        save_schedule_changes(&transaction, group_name, &changes).await?;
+
+        let payload = match schedule_changed_payload(group_name, &changes) {
+            Ok(v) => v,
+            Err(err) => {
+                error!("failed to serialize webhook payload: {}", err);
+                return Err(PersistenceError::FailedToSave);
+            }
+        };
+        enqueue_webhook_deliveries(&transaction, group_name, &payload).await?;
+    }
+
+    notify_schedule_updated(&transaction, group_name).await?;
+    transaction.commit().await?;
+
+    Ok(changes)
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+// makes sure there is only one call in flight for a key. Everyone else asking for the same key while it is
+// running waits for its result instead of doing the same work again.
+pub struct SingleFlight<T> {
+    in_flight: Mutex<HashMap<String, broadcast::Sender<T>>>,
+}
+
+// removes the key even if the call was cancelled (client went away), so that waiters can take over
+struct InFlightGuard<'a, T> {
+    single_flight: &'a SingleFlight<T>,
+    key: String,
+    finished: bool, // key was removed by finish, another call may have added it again since then
+}
+
+impl<T: Clone> SingleFlight<T> {
+
+    pub fn new() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // second value is true if result was produced by another call for the same key
+    pub async fn run<F, Fut>(&self, key: &str, f: F) -> (T, bool) where F: FnOnce() -> Fut, Fut: Future<Output = T> {
+        loop {
+            let receiver = {
+                let mut in_flight = self.in_flight.lock().unwrap();
+                match in_flight.get(key) {
+                    Some(sender) => sender.subscribe(),
+                    None => {
+                        let (sender, _) = broadcast::channel(1);
+                        in_flight.insert(key.to_string(), sender);
+                        break;
+                    }
+                }
+            };
+
+            if let Some(value) = Self::wait_for(receiver).await {
+                return (value, true);
+            }
+            // call we were waiting for was cancelled, try to run it ourselves
+        }
+
+        let guard = InFlightGuard {
+            single_flight: self,
+            key: key.to_string(),
+            finished: false,
+        };
+
+        let value = f().await;
+
+        if let Some(sender) = guard.finish() {
+            // error only means nobody is waiting
+            let _ = sender.send(value.clone());
+        }
+
+        (value, false)
+    }
+
+    async fn wait_for(mut receiver: broadcast::Receiver<T>) -> Option<T> {
+        receiver.recv().await.ok()
+    }
+}
+
+impl<T: Clone> Default for SingleFlight<T> {
+
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> InFlightGuard<'_, T> {
+
+    fn finish(mut self) -> Option<broadcast::Sender<T>> {
+        self.finished = true;
+        self.single_flight.in_flight.lock().unwrap().remove(&self.key)
+    }
+}
+
+impl<T> Drop for InFlightGuard<'_, T> {
+
+    fn drop(&mut self) {
+        if !self.finished {
+            self.single_flight.in_flight.lock().unwrap().remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn concurrent_calls_are_coalesced() {
+        let single_flight = Arc::new(SingleFlight::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..10).map(|_| {
+            let single_flight = single_flight.clone();
+            let calls = calls.clone();
+
+            tokio::spawn(async move {
+                single_flight.run("ІП-82", || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    42
+                }).await
+            })
+        }).collect();
+
+        let mut coalesced = 0;
+        for task in tasks {
+            let (value, is_coalesced) = task.await.unwrap();
+            assert_eq!(value, 42);
+            if is_coalesced {
+                coalesced += 1;
+            }
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(coalesced, 9);
+    }
+
+    #[tokio::test]
+    async fn call_started_after_finished_one_is_coalesced() {
+        let single_flight = Arc::new(SingleFlight::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        assert_eq!(single_flight.run("ІП-82", || async { 1 }).await, (1, false));
+
+        let tasks: Vec<_> = (0..2).map(|i| {
+            let single_flight = single_flight.clone();
+            let calls = calls.clone();
+
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(i * 20)).await;
+                single_flight.run("ІП-82", || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    2
+                }).await
+            })
+        }).collect();
+
+        let mut results = Vec::new();
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+
+        assert_eq!(results, vec![(2, false), (2, true)]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(single_flight.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn waiter_takes_over_cancelled_call() {
+        let single_flight = Arc::new(SingleFlight::new());
+
+        let cancelled = {
+            let single_flight = single_flight.clone();
+            tokio::spawn(async move {
+                single_flight.run("ІП-82", || async {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    1
+                }).await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let waiter = {
+            let single_flight = single_flight.clone();
+            tokio::spawn(async move {
+                single_flight.run("ІП-82", || async { 2 }).await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        cancelled.abort();
+
+        assert_eq!(waiter.await.unwrap(), (2, false));
+    }
+}
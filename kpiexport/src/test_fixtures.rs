@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+// Files in tests/fixtures, a directory for each source:
+//
+//   rozklad_parser  pages recorded from rozklad.kpi.ua
+//   rozklad_api     responses recorded from api.rozklad.org.ua
+//   campus_api      responses in the format of api.campus.kpi.ua
+//   custom_import   spreadsheets in the layout custom::importer reads
+//
+// Expected results are kept next to the input as <name>.expected.json.
+pub fn fixture_path(path: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), path)
+}
+
+pub fn fixture(path: &str) -> String {
+    std::fs::read_to_string(fixture_path(path)).unwrap()
+}
+
+// path is without extension, "rozklad_parser/first_term" is compared with rozklad_parser/first_term.expected.json
+pub fn assert_matches_golden<T: Serialize + ?Sized>(value: &T, path: &str) {
+    let expected: serde_json::Value = serde_json::from_str(&fixture(&format!("{}.expected.json", path))).unwrap();
+    assert_eq!(serde_json::to_value(value).unwrap(), expected);
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Offset, TimeZone, Utc};
+use chrono_tz::Europe::Kiev; // Europe/Kyiv in newer tz database releases
+use crate::models::academic_calendar::AcademicCalendar;
+use crate::models::lesson_times::LessonTimes;
+use crate::models::schedule::{GroupSchedule, GroupScheduleEntry};
+
+// how far ahead to look for the next lesson, covers winter holidays before the term starts
+const NEXT_LESSON_SEARCH_DAYS: i64 = 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledLesson {
+    #[serde(flatten)]
+    pub entry: GroupScheduleEntry,
+    pub starts_at: DateTime<FixedOffset>,
+    pub ends_at: DateTime<FixedOffset>,
+}
+
+// lessons happening on the date according to week parity, sorted by start time
+pub fn lessons_on(schedule: &GroupSchedule, calendar: &AcademicCalendar, lesson_times: &LessonTimes, date: NaiveDate) -> Vec<ScheduledLesson> {
+    let term = calendar.term_at(date);
+    if !term.contains(date) {
+        return Vec::new();
+    }
+
+    if let Some(exclusion) = calendar.exclusion_at(date) {
+        debug!("no lessons on {}: {:?} ({})", date, exclusion.kind, exclusion.description);
+        return Vec::new();
+    }
+
+    let week = term.week(date);
+    let day_index = date.weekday().num_days_from_monday() as u8;
+
+    let mut lessons: Vec<ScheduledLesson> = schedule.entries.iter()
+        .filter(|entry| entry.week == week && entry.day.to_index() == day_index)
+        .filter_map(|entry| {
+            let lesson_time = lesson_times.for_index(entry.index)?;
+
+            Some(ScheduledLesson {
+                entry: entry.clone().with_lesson_time(lesson_time),
+                starts_at: local_datetime(date, lesson_time.start)?,
+                ends_at: local_datetime(date, lesson_time.end)?,
+            })
+        })
+        .collect();
+
+    lessons.sort_by_key(|v| v.starts_at);
+    lessons
+}
+
+// date in Kyiv, terms and week parity change at midnight there and not in UTC
+pub fn kyiv_date(at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&Kiev).date().naive_local()
+}
+
+pub fn current_lesson(schedule: &GroupSchedule, calendar: &AcademicCalendar, lesson_times: &LessonTimes, at: DateTime<Utc>) -> Option<ScheduledLesson> {
+    let date = kyiv_date(at);
+
+    lessons_on(schedule, calendar, lesson_times, date).into_iter()
+        .find(|lesson| lesson.starts_at.with_timezone(&Utc) <= at && at < lesson.ends_at.with_timezone(&Utc))
+}
+
+pub fn next_lesson(schedule: &GroupSchedule, calendar: &AcademicCalendar, lesson_times: &LessonTimes, at: DateTime<Utc>) -> Option<ScheduledLesson> {
+    let today = kyiv_date(at);
+    // the schedule is the one of the current (or upcoming) term, lessons of the next term are not known from it
+    let term_ends_on = calendar.term_at(today).ends_on;
+
+    (0..NEXT_LESSON_SEARCH_DAYS)
+        .map(|offset| today + Duration::days(offset))
+        .take_while(|date| *date <= term_ends_on)
+        .flat_map(|date| lessons_on(schedule, calendar, lesson_times, date))
+        .find(|lesson| lesson.starts_at.with_timezone(&Utc) > at)
+}
+
+fn local_datetime(date: NaiveDate, time: NaiveTime) -> Option<DateTime<FixedOffset>> {
+    Kiev.from_local_datetime(&date.and_time(time)).earliest()
+        .map(|v| v.with_timezone(&v.offset().fix()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::academic_calendar::{AcademicTerm, CalendarExclusion, ExclusionKind, Term};
+    use crate::models::schedule::{GroupScheduleSource, ScheduleDay, ScheduleWeek};
+
+    fn test_schedule() -> GroupSchedule {
+        GroupSchedule {
+            entries: vec![
+                GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 1)
+                    .with_names(vec!["Бази даних".into()]),
+                GroupScheduleEntry::new(ScheduleWeek::First, ScheduleDay::Monday, 0)
+                    .with_names(vec!["Математичний аналіз".into()]),
+                GroupScheduleEntry::new(ScheduleWeek::Second, ScheduleDay::Tuesday, 2)
+                    .with_names(vec!["Фізика".into()]),
+            ],
+            source: Some(GroupScheduleSource::Parser),
+            updated_at: None,
+            stale: false,
+            cancelled_entries: Vec::new(),
+            exceptions: Vec::new(),
+        }
+    }
+
+    fn test_calendar() -> AcademicCalendar {
+        AcademicCalendar::new(
+            vec![AcademicTerm {
+                academic_year: 2021,
+                term: Term::First,
+                starts_on: NaiveDate::from_ymd(2021, 9, 1),
+                ends_on: NaiveDate::from_ymd(2021, 12, 24),
+                first_week_starts_on: NaiveDate::from_ymd(2021, 8, 30),
+            }],
+            vec![CalendarExclusion {
+                starts_on: NaiveDate::from_ymd(2021, 9, 13),
+                ends_on: NaiveDate::from_ymd(2021, 9, 13),
+                kind: ExclusionKind::Holiday,
+                description: "test holiday".into(),
+            }]
+        )
+    }
+
+    fn at(datetime: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(datetime).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn current_lesson_uses_kyiv_time() {
+        // 2021-09-27 is Monday of the first week
+        let lesson = current_lesson(&test_schedule(), &test_calendar(), &LessonTimes::standard(), at("2021-09-27T06:00:00Z")).unwrap();
+
+        assert_eq!(lesson.entry.names, vec!["Математичний аналіз".to_string()]);
+        assert_eq!(lesson.starts_at.to_rfc3339(), "2021-09-27T08:30:00+03:00");
+        assert_eq!(lesson.ends_at.to_rfc3339(), "2021-09-27T10:10:00+03:00");
+
+        // break between lessons
+        assert!(current_lesson(&test_schedule(), &test_calendar(), &LessonTimes::standard(), at("2021-09-27T07:15:00Z")).is_none());
+    }
+
+    #[test]
+    fn next_lesson_respects_week_parity_and_holidays() {
+        // 2021-09-06 is Monday of the second week
+        let lesson = next_lesson(&test_schedule(), &test_calendar(), &LessonTimes::standard(), at("2021-09-06T06:00:00Z")).unwrap();
+        assert_eq!(lesson.entry.names, vec!["Фізика".to_string()]);
+        assert_eq!(lesson.starts_at.to_rfc3339(), "2021-09-07T12:20:00+03:00");
+
+        // Monday 2021-09-13 is a holiday, so next lesson is on Tuesday of the following week
+        let lesson = next_lesson(&test_schedule(), &test_calendar(), &LessonTimes::standard(), at("2021-09-08T06:00:00Z")).unwrap();
+        assert_eq!(lesson.starts_at.to_rfc3339(), "2021-09-21T12:20:00+03:00");
+    }
+
+    #[test]
+    fn next_lesson_is_not_searched_in_next_term() {
+        let calendar = AcademicCalendar::new(
+            vec![
+                AcademicTerm {
+                    academic_year: 2021,
+                    term: Term::First,
+                    starts_on: NaiveDate::from_ymd(2021, 9, 1),
+                    ends_on: NaiveDate::from_ymd(2021, 12, 24),
+                    first_week_starts_on: NaiveDate::from_ymd(2021, 8, 30),
+                },
+                AcademicTerm {
+                    academic_year: 2021,
+                    term: Term::Second,
+                    starts_on: NaiveDate::from_ymd(2022, 1, 3),
+                    ends_on: NaiveDate::from_ymd(2022, 5, 31),
+                    first_week_starts_on: NaiveDate::from_ymd(2022, 1, 3),
+                },
+            ],
+            Vec::new()
+        );
+
+        // Friday 2021-12-24 is the last day of the first term, there are no lessons left in it
+        assert!(next_lesson(&test_schedule(), &calendar, &LessonTimes::standard(), at("2021-12-22T06:00:00Z")).is_none());
+
+        // between terms, the schedule is the one of the upcoming term
+        let lesson = next_lesson(&test_schedule(), &calendar, &LessonTimes::standard(), at("2021-12-28T06:00:00Z")).unwrap();
+        assert_eq!(lesson.starts_at.to_rfc3339(), "2022-01-03T08:30:00+02:00");
+    }
+}
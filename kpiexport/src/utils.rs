@@ -1,4 +1,3 @@
-use chrono::{DateTime, Utc, Datelike};
 use crate::errors::RozkladParseError;
 
 const VIEW_SCHEDULE_PREFIX: &'static str = "ViewSchedule.aspx?g=";
@@ -10,8 +9,20 @@ pub fn group_id_from_url(url: &str) -> Result<String, RozkladParseError> {
     }
 }
 
-pub fn is_hot_month() -> bool {
-    // in January, February, August and September refresh every 2-3 days, other months - every ~20 days
-    let utc: DateTime<Utc> = Utc::now();
-    utc.month0() <= 1 || utc.month0() == 7 || utc.month0() == 8
-}
\ No newline at end of file
+// time taken does not depend on where the values differ, for comparing secrets
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_compared_fully() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+}
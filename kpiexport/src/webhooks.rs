@@ -0,0 +1,333 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use sha2::Sha256;
+use tokio_postgres::Client;
+use crate::models::schedule_changes::ScheduleChange;
+use crate::models::webhooks::{claim_due_webhook_deliveries, record_webhook_delivery_attempt, WebhookDelivery};
+
+pub const SIGNATURE_HEADER: &str = "X-Kpiexport-Signature"; // "sha256=" and hex HMAC of the body with subscription secret
+pub const DELIVERY_HEADER: &str = "X-Kpiexport-Delivery"; // same for retries of the same payload
+
+const MAX_ATTEMPTS: i32 = 8;
+const FIRST_RETRY_DELAY_SECONDS: i64 = 30;
+const DELIVERIES_PER_BATCH: i64 = 20;
+const DELIVERY_LEASE_SECONDS: i64 = 120; // longer than a batch of timed out requests
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize)]
+struct ScheduleChangedEvent<'a> {
+    event: &'static str,
+    group_name: &'a str,
+    changes: &'a [ScheduleChange],
+    detected_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct DeliveryResult {
+    pub response_status: Option<u16>,
+    pub error: Option<String>, // None if delivered
+}
+
+pub fn schedule_changed_payload(group_name: &str, changes: &[ScheduleChange]) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&ScheduleChangedEvent {
+        event: "schedule.changed",
+        group_name,
+        changes,
+        detected_at: Utc::now(),
+    })
+}
+
+// shown to the client once, when subscription is created
+pub fn new_webhook_secret() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret.iter().map(|v| format!("{:02x}", v)).collect()
+}
+
+pub fn sign_payload(secret: &str, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("hmac accepts keys of any length");
+    mac.update(payload.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+// Connects to the address which was checked, not to what the host resolves to by the time of the request, so
+// a subscriber can not switch its domain to a private address after the check. Redirects are not followed for the
+// same reason.
+fn webhook_client(host: &str, address: SocketAddr) -> reqwest::Client {
+    reqwest::Client::builder()
+        .redirect(Policy::none())
+        .resolve(host, address)
+        .build()
+        .expect("webhook client has valid configuration")
+}
+
+// Subscribers are outside of our network, so http(s) urls with hosts which are not loopback or private addresses.
+// Domain names are checked again with the addresses they resolve to when webhooks are sent.
+pub fn is_allowed_webhook_url(url: &str) -> bool {
+    let url = match Url::parse(url) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return false;
+    }
+
+    let host = match url_host(&url) {
+        Some(v) => v,
+        None => return false,
+    };
+
+    match host.parse::<IpAddr>() {
+        Ok(address) => is_public_address(address),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost")
+        },
+    }
+}
+
+// ipv6 addresses are written in brackets in urls
+fn url_host(url: &Url) -> Option<&str> {
+    url.host_str()
+        .filter(|v| !v.is_empty())
+        .map(|v| v.trim_start_matches('[').trim_end_matches(']'))
+}
+
+// host of the url and the address to send webhooks to, None unless all addresses of the host are public
+async fn public_address(url: &str) -> Option<(String, SocketAddr)> {
+    let url = Url::parse(url).ok().filter(|v| is_allowed_webhook_url(v.as_str()))?;
+    let host = url_host(&url)?.to_string();
+    let port = url.port_or_known_default()?;
+
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port)).await.ok()?.collect();
+    if !addresses.iter().all(|v| is_public_address(v.ip())) {
+        return None;
+    }
+
+    addresses.first().map(|address| (host, *address))
+}
+
+fn is_public_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => {
+            let octets = address.octets();
+            let is_shared = octets[0] == 100 && (octets[1] & 0xc0) == 64; // 100.64.0.0/10, carrier-grade nat
+
+            !(address.is_private() || address.is_loopback() || address.is_link_local() || address.is_unspecified()
+                || address.is_broadcast() || address.is_multicast() || address.is_documentation() || is_shared
+                || octets[0] == 0)
+        },
+        IpAddr::V6(address) => {
+            if let Some(address) = address.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(address));
+            }
+
+            let first_segment = address.segments()[0];
+            let is_unique_local = (first_segment & 0xfe00) == 0xfc00; // fc00::/7
+            let is_link_local = (first_segment & 0xffc0) == 0xfe80; // fe80::/10
+
+            !(address.is_loopback() || address.is_unspecified() || address.is_multicast() || is_unique_local || is_link_local)
+        },
+    }
+}
+
+// 30 seconds after the first failed attempt, doubled after each next one
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    chrono::Duration::seconds(FIRST_RETRY_DELAY_SECONDS << (attempts - 1).clamp(0, 16))
+}
+
+pub async fn send_webhook(client: &reqwest::Client, delivery: &WebhookDelivery) -> DeliveryResult {
+    let response = client.post(&delivery.url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, sign_payload(&delivery.secret, &delivery.payload))
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .timeout(DELIVERY_TIMEOUT)
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => DeliveryResult {
+            response_status: Some(response.status().as_u16()),
+            error: None,
+        },
+        Ok(response) => DeliveryResult {
+            response_status: Some(response.status().as_u16()),
+            error: Some(format!("unexpected response status: {}", response.status())),
+        },
+        Err(err) => DeliveryResult {
+            response_status: None,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+// sends everything that is due, returns the number of attempts made
+pub async fn deliver_due_webhooks(database: &Client) -> Result<usize, tokio_postgres::Error> {
+    let mut total_attempts = 0;
+
+    loop {
+        let deliveries = claim_due_webhook_deliveries(database, DELIVERIES_PER_BATCH, DELIVERY_LEASE_SECONDS).await?;
+        if deliveries.is_empty() {
+            return Ok(total_attempts);
+        }
+
+        for delivery in &deliveries {
+            let result = match public_address(&delivery.url).await {
+                Some((host, address)) => send_webhook(&webhook_client(&host, address), delivery).await,
+                None => DeliveryResult {
+                    response_status: None,
+                    error: Some("webhook url does not resolve to public addresses".to_string()),
+                },
+            };
+            let attempts = delivery.attempts + 1;
+
+            let next_attempt_at = match &result.error {
+                Some(err) => {
+                    warn!("webhook delivery {} to {} failed (attempt {}): {}", delivery.id, delivery.url, attempts, err);
+                    if attempts < MAX_ATTEMPTS {
+                        Some(Utc::now() + retry_delay(attempts))
+                    } else {
+                        None
+                    }
+                },
+                None => None,
+            };
+
+            record_webhook_delivery_attempt(
+                database,
+                delivery.id,
+                result.response_status.map(i32::from),
+                result.error.as_deref(),
+                result.error.is_none(),
+                next_attempt_at
+            ).await?;
+        }
+
+        total_attempts += deliveries.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{post, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
+
+    const SECRET: &str = "test secret";
+
+    #[post("/hook")]
+    async fn hook(req: HttpRequest, body: String) -> impl Responder {
+        let signature = req.headers().get(SIGNATURE_HEADER).and_then(|v| v.to_str().ok());
+        if signature == Some(sign_payload(SECRET, &body).as_str()) {
+            HttpResponse::Ok().finish()
+        } else {
+            HttpResponse::Unauthorized().finish()
+        }
+    }
+
+    #[post("/broken")]
+    async fn broken() -> impl Responder {
+        HttpResponse::InternalServerError().finish()
+    }
+
+    // local stand-in for a subscriber, returns its base url
+    fn start_subscriber() -> String {
+        let server = HttpServer::new(|| App::new().service(hook).service(broken).default_service(web::to(HttpResponse::NotFound)))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let address = server.addrs()[0];
+        actix_rt::spawn(server.run());
+        format!("http://{}", address)
+    }
+
+    fn delivery(url: String, secret: &str) -> WebhookDelivery {
+        WebhookDelivery {
+            id: 1,
+            url,
+            secret: secret.to_string(),
+            payload: schedule_changed_payload("ІП-82", &[]).unwrap(),
+            attempts: 0,
+        }
+    }
+
+    #[test]
+    fn payload_is_signed_with_hmac_sha256() {
+        assert_eq!(
+            sign_payload("key", "The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn retry_delay_grows_exponentially() {
+        assert_eq!(retry_delay(1), chrono::Duration::seconds(30));
+        assert_eq!(retry_delay(2), chrono::Duration::seconds(60));
+        assert_eq!(retry_delay(7), chrono::Duration::seconds(30 * 64));
+    }
+
+    #[test]
+    fn webhook_urls_are_limited_to_public_hosts() {
+        assert!(is_allowed_webhook_url("https://example.com/hook"));
+        assert!(is_allowed_webhook_url("http://8.8.8.8:8080/hook"));
+        assert!(is_allowed_webhook_url("https://[2001:4860:4860::8888]/hook"));
+
+        assert!(!is_allowed_webhook_url("ftp://example.com/hook"));
+        assert!(!is_allowed_webhook_url("not a url"));
+        assert!(!is_allowed_webhook_url("http://localhost:8080/hook"));
+        assert!(!is_allowed_webhook_url("http://api.localhost./hook"));
+        assert!(!is_allowed_webhook_url("http://127.0.0.1/hook"));
+        assert!(!is_allowed_webhook_url("http://10.0.0.5/hook"));
+        assert!(!is_allowed_webhook_url("http://192.168.1.1/hook"));
+        assert!(!is_allowed_webhook_url("http://169.254.169.254/latest/meta-data"));
+        assert!(!is_allowed_webhook_url("http://0.0.0.0/hook"));
+        assert!(!is_allowed_webhook_url("http://[::1]/hook"));
+        assert!(!is_allowed_webhook_url("http://[fd00::1]/hook"));
+        assert!(!is_allowed_webhook_url("http://[::ffff:127.0.0.1]/hook"));
+    }
+
+    #[actix_rt::test]
+    async fn loopback_subscriber_is_not_resolved_as_public() {
+        assert!(public_address(&format!("{}/hook", start_subscriber())).await.is_none());
+        assert!(public_address("http://localhost/hook").await.is_none());
+        assert_eq!(
+            public_address("http://8.8.8.8:8080/hook").await,
+            Some(("8.8.8.8".to_string(), "8.8.8.8:8080".parse().unwrap()))
+        );
+    }
+
+    #[actix_rt::test]
+    async fn webhook_is_sent_to_checked_address() {
+        let address: SocketAddr = start_subscriber().trim_start_matches("http://").parse().unwrap();
+        let client = webhook_client("subscriber.invalid", address);
+
+        // the domain does not resolve, the request can only go to the given address
+        let result = send_webhook(&client, &delivery(format!("http://subscriber.invalid:{}/hook", address.port()), SECRET)).await;
+        assert_eq!(result.response_status, Some(200));
+    }
+
+    #[actix_rt::test]
+    async fn webhook_is_delivered_to_subscriber() {
+        let base_url = start_subscriber();
+        let client = reqwest::Client::new();
+
+        let result = send_webhook(&client, &delivery(format!("{}/hook", base_url), SECRET)).await;
+        assert!(result.error.is_none());
+        assert_eq!(result.response_status, Some(200));
+
+        let result = send_webhook(&client, &delivery(format!("{}/hook", base_url), "other secret")).await;
+        assert_eq!(result.response_status, Some(401));
+
+        let result = send_webhook(&client, &delivery(format!("{}/broken", base_url), SECRET)).await;
+        assert!(result.error.is_some());
+        assert_eq!(result.response_status, Some(500));
+    }
+}
@@ -0,0 +1,26 @@
+/*
+ * Generated by util/mkerr.pl DO NOT EDIT
+ * Copyright 1995-2021 The OpenSSL Project Authors. All Rights Reserved.
+ *
+ * Licensed under the Apache License 2.0 (the "License").  You may not use
+ * this file except in compliance with the License.  You can obtain a copy
+ * in the file LICENSE in the source distribution or at
+ * https://www.openssl.org/source/license.html
+ */
+
+#ifndef OPENSSL_OBJECTSERR_H
+#define OPENSSL_OBJECTSERR_H
+#pragma once
+
+#include <openssl/opensslconf.h>
+#include <openssl/symhacks.h>
+#include <openssl/cryptoerr_legacy.h>
+
+/*
+ * OBJ reason codes.
+ */
+#define OBJ_R_OID_EXISTS 102
+#define OBJ_R_UNKNOWN_NID 101
+#define OBJ_R_UNKNOWN_OBJECT_NAME 103
+
+#endif
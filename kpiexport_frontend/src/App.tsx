@@ -15,12 +15,13 @@ import {
     GetScheduleResponse,
     GoogleOAuthToken,
     GroupScheduleEntry,
+    LessonType,
 } from './types';
 import {oauthClientId} from './constants';
 import moment from 'moment';
 
 export const App = () => {
-    const [error] = useState<string|undefined>(undefined);
+    const [error, setError] = useState<string|undefined>(undefined);
     const [screen, setScreen] = useState<Screen>('input');
 
     const [selectedGroup, setSelectedGroup] = useState<string>('');
@@ -35,7 +36,7 @@ export const App = () => {
         setProgressTotal(total);
     };
 
-    const exportScheduleFn = exportSchedule(setScreen, updateProgress);
+    const exportScheduleFn = exportSchedule(setScreen, setError, updateProgress);
 
     return (
         <>
@@ -83,7 +84,9 @@ const screenElementByType = (type: Screen,
     }
 };
 
-const exportSchedule = (setScreen: (s: Screen) => void, updateProgress: (progress: number, total: number) => void) =>
+const exportSchedule = (setScreen: (s: Screen) => void,
+                        setError: (error: string|undefined) => void,
+                        updateProgress: (progress: number, total: number) => void) =>
     async (groupName: string, calendarName: string, studentName: string) => {
     if (localStorage.authDone === undefined) {
         localStorage.authDone = true;
@@ -92,7 +95,16 @@ const exportSchedule = (setScreen: (s: Screen) => void, updateProgress: (progres
     }
 
     const token = await get_google_token();
-    const schedule = await scheduleForGroup(groupName, studentName);
+
+    let schedule: GetScheduleResponse;
+    try {
+        schedule = await scheduleForGroup(groupName, studentName);
+    } catch (e) {
+        setError(e instanceof Error ? e.message : 'Failed to load schedule, please try again later');
+        setScreen('input');
+        return;
+    }
+    setError(undefined);
 
     const progressTotal = schedule.entries.length + 1;
     let progressCounter = 0;
@@ -154,12 +166,12 @@ const create_calendar_event = (entry: GroupScheduleEntry): CalendarEntry => {
 
     const formatTime = (time: string) => moment(time, 'HH:mm').utc().format('HH:mm');
 
-    const timeStart = formatTime(lecture_start_time(entry.index));
-    const timeEnd = formatTime(lecture_end_time(entry.index));
+    const timeStart = formatTime(entry.start_time || lecture_start_time(entry.index));
+    const timeEnd = formatTime(entry.end_time || lecture_end_time(entry.index));
 
     return {
         summary: lessonName,
-        description: `${lessonName}\nВикладач: ${lecturerName}`,
+        description: `${lessonName}\nВикладач: ${lecturerName}${entry.lesson_type ? `\nТип: ${lesson_type_name(entry.lesson_type)}` : ''}`,
         start: {
             dateTime: `${moment().year()}${isSecondSemester ? '-02-' : '-09-'}${daystr}T${timeStart}:00.000Z`,
             timeZone: 'Europe/Kiev'
@@ -175,6 +187,17 @@ const create_calendar_event = (entry: GroupScheduleEntry): CalendarEntry => {
     };
 };
 
+const lesson_type_name = (lessonType: LessonType): string => {
+    switch (lessonType) {
+        case 'lecture':
+            return 'Лекція';
+        case 'practice':
+            return 'Практика';
+        case 'lab':
+            return 'Лабораторна';
+    }
+};
+
 const lecture_start_time = (index: number): string => {
     switch (index) {
         case 0:
@@ -268,15 +291,32 @@ const get_google_token = (): Promise<GoogleOAuthToken> => new Promise((resolve,
 
 const scheduleForGroup = async (groupName: string, studentName: string): Promise<GetScheduleResponse> => new Promise<GetScheduleResponse>((resolve, reject) => {
     const reqUrl = (studentName || '').trim() !== ''
-        ? `/v1/schedule/${groupName}?lastName=${studentName}`
-        : `/groups/${groupName}`;
+        ? `/v1/schedule/${encodeURIComponent(groupName)}?lastName=${encodeURIComponent(studentName)}`
+        : `/groups/${encodeURIComponent(groupName)}`;
 
     const req = new XMLHttpRequest();
     req.open('GET', reqUrl, true);
     req.onreadystatechange = () => {
-        if (req.readyState === 4 && req.status === 200) {
+        if (req.readyState !== 4) {
+            return;
+        }
+
+        if (req.status === 200) {
             resolve(JSON.parse(req.responseText) as GetScheduleResponse);
+        } else {
+            reject(new Error(scheduleErrorText(req.status, req.responseText)));
         }
     };
     req.send(null);
 });
+
+// api responds with error codes like "student_not_found" in the body
+const scheduleErrorText = (status: number, body: string): string => {
+    if (body === 'student_not_found') {
+        return 'No student with this last name in the group. Check the name or leave it empty to export the full group schedule';
+    } else if (body === 'student_name_ambiguous') {
+        return 'Several students in the group have this last name, please type it in full';
+    }
+
+    return 'Failed to load schedule, please try again later';
+};
@@ -7,6 +7,7 @@ export type GetGroupsResponse = string[];
 export type GetScheduleResponse = {
     entries: GroupScheduleEntry[],
 };
+export type LessonType = 'lecture' | 'practice' | 'lab';
 export type GroupScheduleEntry = {
     week: number,
     day: number,
@@ -14,6 +15,9 @@ export type GroupScheduleEntry = {
     names: string[],
     lecturers: string[],
     locations: string[],
+    lesson_type?: LessonType,
+    start_time?: string,
+    end_time?: string,
 };
 
 export type CreateCalendarResponse = {